@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -9,11 +10,16 @@ pub struct Args {
 }
 
 #[derive(StructOpt)]
+#[allow(clippy::large_enum_variant)]
 pub enum Command {
     /// Download podcast episodes
     Download(Download),
     /// Print podcast info
     Print(Print),
+    /// List supported sites and the urls they handle
+    ListExtractors,
+    /// Generate an rss feed for downloaded episodes
+    GenerateFeed(GenerateFeed),
     /// Search for a podcast
     #[cfg(feature = "search")]
     Search(Search),
@@ -27,15 +33,67 @@ pub struct Download {
     /// Amount of episodes to download
     #[structopt(long)]
     pub limit: Option<usize>,
-    /// Offset in episodes to download
+    /// Amount of episodes to skip, counted after filtering and sorting
     #[structopt(long)]
     pub offset: Option<usize>,
+    /// Episode numbers to download, like 1-10,42,100-
+    ///
+    /// Matches <itunes:episode> numbers, leaving out episodes without one like trailers. In feeds
+    /// without episode numbers, matches positions counting from the oldest episode at 1.
+    #[structopt(long)]
+    pub episodes: Option<podcast_dl::EpisodeRanges>,
     /// Sort downloads by oldest instead of newest
     #[structopt(long)]
     pub oldest: bool,
+    /// Only episodes published on or after date (YYYY-MM-DD)
+    #[structopt(long)]
+    pub after: Option<chrono::NaiveDate>,
+    /// Only episodes published on or before date (YYYY-MM-DD)
+    #[structopt(long)]
+    pub before: Option<chrono::NaiveDate>,
+    /// Only episodes with titles matching regex
+    #[structopt(long)]
+    pub match_title: Option<regex::Regex>,
+    /// Skip episodes with titles matching regex
+    #[structopt(long)]
+    pub reject_title: Option<regex::Regex>,
+    /// Only episodes at least this long (seconds, MM:SS or HH:MM:SS)
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    pub min_duration: Option<u64>,
+    /// Only episodes at most this long (seconds, MM:SS or HH:MM:SS)
+    #[structopt(long, parse(try_from_str = parse_duration))]
+    pub max_duration: Option<u64>,
+    /// Only episodes of type
+    #[structopt(long, possible_values = podcast_dl::EpisodeType::VARIANTS)]
+    pub episode_type: Option<podcast_dl::EpisodeType>,
+    /// Only episodes marked explicit
+    #[structopt(long, conflicts_with = "clean")]
+    pub explicit: bool,
+    /// Only episodes not marked explicit
+    #[structopt(long)]
+    pub clean: bool,
     /// Output template
+    ///
+    /// Variables: podcast_title, podcast_author, episode_title, episode_author, episode_index,
+    /// episode_number, season, episode_date, episode_duration (seconds), episode_guid and ext.
+    /// Dates take strftime formats like {episode_date:%Y-%m-%d}.
+    /// Filters follow the variable: lower, upper, slug, truncate(n), pad(n), default("x") and
+    /// replace("regex", "replacement"), like {episode_title|replace("^Ep\\. \\d+: ","")|truncate(80)}.
+    /// Sections in [] are left out when a variable in them is missing, like [S{season:02} ].
+    /// Brackets without a variable in them are kept as text. Use {{, }}, [[ and ]] for literal
+    /// braces and brackets; templates written before optional sections need [[ and ]] around
+    /// brackets containing a variable.
     #[structopt(short, long, default_value = "{podcast_title}/{episode_title}.mp3")]
     pub output: String,
+    /// Value of missing variables in the output template
+    #[structopt(long, default_value = "UNKNOWN")]
+    pub output_fallback: String,
+    /// Directory the output template is relative to. Files are never written outside of it.
+    #[structopt(long)]
+    pub output_dir: Option<PathBuf>,
+    /// How strictly to sanitize values in the output template
+    #[structopt(long, default_value = "posix", possible_values = podcast_dl::Sanitize::VARIANTS)]
+    pub sanitize: podcast_dl::Sanitize,
     /// Download cover image
     #[structopt(long)]
     pub download_image: bool,
@@ -45,9 +103,47 @@ pub struct Download {
     /// Write episode description to file
     #[structopt(long)]
     pub write_episode_description: bool,
+    /// Write episode and podcast metadata to <name>.info.json next to each episode
+    #[structopt(long)]
+    pub write_info_json: bool,
+    /// Write a playlist of the selected episodes to the podcast directory, in the order they were selected
+    #[structopt(long)]
+    pub write_playlist: bool,
+    /// Format of the playlist written with --write-playlist
+    #[structopt(long, default_value = "m3u8", possible_values = podcast_dl::PlaylistFormat::VARIANTS)]
+    pub playlist_format: podcast_dl::PlaylistFormat,
+    /// Write metadata, cover image and episode sidecars in the layout of a media server, instead of
+    /// --download-image and --write-description. audiobookshelf implies --embed-metadata.
+    #[structopt(long, possible_values = podcast_dl::MediaServer::VARIANTS)]
+    pub media_server: Option<podcast_dl::MediaServer>,
     /// Remove a string from the output
     #[structopt(long)]
     pub remove_from_output: Vec<String>,
+    /// What to do when the file of an episode exists. if-different can not be combined with
+    /// --embed-metadata or --media-server audiobookshelf, as tags change the size of downloaded files.
+    #[structopt(long, default_value = "never", possible_values = podcast_dl::Overwrite::VARIANTS)]
+    pub overwrite: podcast_dl::Overwrite,
+    /// What to do when episodes have the same path: skip, or append the index, guid or date
+    #[structopt(long, default_value = "skip", possible_values = podcast_dl::Collision::VARIANTS)]
+    pub collision: podcast_dl::Collision,
+    /// Record downloaded episodes in file and skip episodes already in it
+    #[structopt(long)]
+    pub download_archive: Option<PathBuf>,
+    /// Write title, podcast, author, date, description and cover into downloaded files
+    #[structopt(long)]
+    pub embed_metadata: bool,
+    /// Do not check that downloaded episodes are complete audio or video files
+    #[structopt(long)]
+    pub no_verify: bool,
+    /// Show what would be downloaded without writing files or downloading audio
+    #[structopt(long)]
+    pub dry_run: bool,
+    /// Send a HEAD request for every episode in a dry run to show the total download size
+    #[structopt(long, requires = "dry-run")]
+    pub total_size: bool,
+    /// Number of episodes to download at the same time
+    #[structopt(short, long, default_value = "1")]
+    pub jobs: usize,
 }
 
 #[derive(StructOpt)]
@@ -56,8 +152,30 @@ pub struct Print {
     pub url: String,
 }
 
+#[derive(StructOpt)]
+pub struct GenerateFeed {
+    /// Directory with downloaded episodes
+    pub directory: PathBuf,
+    /// Url the directory is served at, used for enclosures and images
+    #[structopt(long)]
+    pub base_url: url::Url,
+    /// File to write the feed to instead of stdout
+    #[structopt(short, long)]
+    pub output: Option<PathBuf>,
+    /// Only include episodes recorded in download archive
+    #[structopt(long)]
+    pub download_archive: Option<PathBuf>,
+}
+
+#[cfg(feature = "search")]
 #[derive(StructOpt)]
 pub struct Search {
     /// Search terms
     pub search_terms: Vec<String>,
 }
+
+/// Parses durations given on the command line
+fn parse_duration(duration: &str) -> Result<u64, String> {
+    podcast_dl::feed::parse_duration(duration)
+        .ok_or_else(|| format!("Invalid duration: {}", duration))
+}
@@ -13,12 +13,36 @@ pub enum Error {
     Parse(#[from] ParseError),
     /// Format string
     StringFormat,
+    /// Invalid output template: {0}
+    InvalidTemplate(String),
+    /// Options can not be combined: {0}
+    ConflictingOptions(&'static str),
     /// Failed to create path
     Path,
+    /// Path {0} is outside of the output directory
+    PathTraversal(String),
+    /// Output path {0} has no file name
+    EmptyFileName(String),
     /// File aldready exists
     FileExists(String),
+    /// File of {0} is the same as on the server
+    Unchanged(String),
+    /// Another episode has the same path as {0}
+    PathCollision(String),
+    /// Episode is in download archive: {0}
+    InArchive(String),
+    /// Download of {0} failed verification: {1}
+    Verification(String, String),
+    /// Failed to write ID3 tag: {0}
+    Id3(#[from] id3::Error),
+    /// Failed to write mp4 metadata: {0}
+    Mp4(#[from] mp4ameta::Error),
     /// Could not write {0} file
     WriteToFile(&'static str),
+    /// Strategy for {0} never parses a podcast
+    IncompleteStrategy(String),
+    /// Could not find episode from {0} in feed
+    EpisodeNotFound(String),
     /// Missing {value} from {from}
     ValueMissing {
         value: &'static str,
@@ -36,6 +60,10 @@ pub enum ParseError {
     UTF8(#[from] std::str::Utf8Error),
     /// Json
     Json(#[from] serde_json::Error),
+    /// Invalid regex: {0}
+    Regex(#[from] regex::Error),
+    /// Decoding base64: {0}
+    Base64(#[from] base64::DecodeError),
     /// Missing element
     MissingElement,
 }
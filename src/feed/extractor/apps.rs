@@ -0,0 +1,158 @@
+//! Extractors for share links from podcast apps and directories
+//!
+//! App pages rarely link to the feed directly, so most of them are resolved through the
+//! Apple Podcasts id they link to.
+
+use crate::{
+    error::ParseError,
+    feed::strategy::Strategy,
+};
+use super::{
+    extractors::{Site, ITUNES_LOOKUP, itunes_feed_url, itunes_lookup},
+    host_matches, path_parts,
+};
+use base64::{
+    Engine,
+    alphabet,
+    engine::{GeneralPurpose, GeneralPurposeConfig, DecodePaddingMode},
+};
+use std::str::FromStr;
+use url::Url;
+
+/// Link to Apple Podcasts in html with the podcast id as the first capture group
+const ITUNES_ID_LINK: &str = r#"(?:podcasts|itunes)\.apple\.com/[^"'\s<>]*?/id(\d+)"#;
+
+pub fn sites() -> Vec<Site> {
+    vec![
+        Site {
+            name: "overcast",
+            patterns: &[
+                "https://overcast.fm/itunes<itunes id>/<name>",
+                "https://overcast.fm/+<episode id>",
+            ],
+            matches: |url| host_matches(url, &["overcast.fm", "www.overcast.fm"]),
+            build: overcast,
+        },
+        Site {
+            name: "pocketcasts",
+            patterns: &[
+                "https://pca.st/<id>",
+                "https://pocketcasts.com/podcast/<name>/<uuid>",
+            ],
+            matches: |url| host_matches(url, &["pca.st", "pocketcasts.com", "www.pocketcasts.com"]),
+            build: itunes_link_in_page,
+        },
+        Site {
+            name: "castro",
+            patterns: &[
+                "https://castro.fm/podcast/<uuid>",
+                "https://castro.fm/episode/<id>",
+            ],
+            matches: |url| host_matches(url, &["castro.fm", "www.castro.fm"]),
+            build: itunes_link_in_page,
+        },
+        Site {
+            name: "castbox",
+            patterns: &[
+                "https://castbox.fm/channel/<name>-id<id>",
+                "https://castbox.fm/episode/<name>-id<channel id>-id<episode id>",
+            ],
+            matches: |url| host_matches(url, &["castbox.fm", "www.castbox.fm"]),
+            build: castbox,
+        },
+        Site {
+            name: "podcastaddict",
+            patterns: &[
+                "https://podcastaddict.com/podcast/<name>/<id>",
+                "https://podcastaddict.com/<name>/episode/<id>",
+            ],
+            matches: |url| host_matches(url, &["podcastaddict.com", "www.podcastaddict.com"]),
+            build: podcast_addict,
+        },
+        Site {
+            name: "podcastindex",
+            patterns: &[
+                "https://podcastindex.org/podcast/<id>",
+            ],
+            matches: |url| host_matches(url, &["podcastindex.org", "www.podcastindex.org"])
+                && path_parts(url).first() == Some(&"podcast"),
+            build: podcast_index,
+        },
+        Site {
+            name: "googlepodcasts",
+            patterns: &[
+                "https://podcasts.google.com/feed/<base64 feed url>",
+                "https://podcasts.google.com/feed/<base64 feed url>/episode/<id>",
+                "https://www.google.com/podcasts?feed=<base64 feed url>",
+            ],
+            matches: |url| host_matches(url, &["podcasts.google.com"])
+                || (host_matches(url, &["www.google.com", "google.com"])
+                    && path_parts(url).first() == Some(&"podcasts")),
+            build: google_podcasts,
+        },
+    ]
+}
+
+fn itunes_link_in_page(url: &Url) -> Result<Strategy, ParseError> {
+    Ok(Strategy::from_url(url.clone())
+        .find_format(ITUNES_ID_LINK, ITUNES_LOOKUP)
+        .json(itunes_feed_url())
+        .rss())
+}
+
+fn overcast(url: &Url) -> Result<Strategy, ParseError> {
+    let itunes_id = path_parts(url).first()
+        .and_then(|part| part.strip_prefix("itunes"));
+    match itunes_id {
+        Some(itunes_id) => itunes_lookup(itunes_id),
+        // Episode pages link back to the podcast page
+        None => Ok(Strategy::from_url(url.clone())
+            .find_format(r"/itunes(\d+)", ITUNES_LOOKUP)
+            .json(itunes_feed_url())
+            .rss()),
+    }
+}
+
+fn castbox(url: &Url) -> Result<Strategy, ParseError> {
+    Ok(Strategy::from_url(url.clone())
+        .find(r#""rss_url"\s*:\s*"([^"]+)""#)
+        .rss())
+}
+
+fn podcast_addict(url: &Url) -> Result<Strategy, ParseError> {
+    Ok(Strategy::from_url(url.clone())
+        .find(r#"href="([^"]+)"[^>]*>\s*RSS\b"#)
+        .rss())
+}
+
+fn podcast_index(url: &Url) -> Result<Strategy, ParseError> {
+    let id = path_parts(url).get(1).copied()
+        .ok_or(ParseError::MissingElement)?;
+    Ok(Strategy::from_str(&format!("https://podcastindex.org/api/podcasts/byfeedid?id={}", id))?
+        .json(vec!["feed", "url"])
+        .rss())
+}
+
+/// Google Podcasts links contain the feed url encoded as base64
+fn google_podcasts(url: &Url) -> Result<Strategy, ParseError> {
+    let encoded = match path_parts(url).as_slice() {
+        ["feed", encoded, ..] => encoded.to_string(),
+        _ => url.query_pairs()
+            .find(|(key, _)| key == "feed")
+            .map(|(_, value)| value.into_owned())
+            .ok_or(ParseError::MissingElement)?,
+    };
+    let feed_url = decode_base64(&encoded)?;
+    Ok(Strategy::from_str(&feed_url)?.rss())
+}
+
+/// Decodes both standard and url safe base64 with or without padding
+fn decode_base64(input: &str) -> Result<String, ParseError> {
+    let engine = GeneralPurpose::new(
+        &alphabet::STANDARD,
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    );
+    let normalized = input.replace('-', "+").replace('_', "/");
+    let bytes = engine.decode(normalized)?;
+    Ok(std::str::from_utf8(&bytes)?.to_string())
+}
@@ -0,0 +1,136 @@
+use crate::{
+    error::ParseError,
+    feed::strategy::{Strategy, UrlOperation, EpisodeOperation},
+};
+use super::{Extractor, host_matches, last_path_part, path_parts, apps, hosting};
+use std::str::FromStr;
+use url::Url;
+
+/// Extractor for a single site built from plain functions
+pub struct Site {
+    pub name: &'static str,
+    pub patterns: &'static [&'static str],
+    pub matches: fn(&Url) -> bool,
+    pub build: fn(&Url) -> Result<Strategy, ParseError>,
+}
+
+impl Extractor for Site {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn patterns(&self) -> &[&str] {
+        self.patterns
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        (self.matches)(url)
+    }
+
+    fn build(&self, url: &Url) -> Result<Strategy, ParseError> {
+        (self.build)(url)
+    }
+}
+
+/// All built-in site specific extractors
+pub fn builtin() -> Vec<Box<dyn Extractor>> {
+    [
+        Site {
+            name: "rss.com",
+            patterns: &[
+                "https://rss.com/podcasts/<name>",
+                "https://rss.com/podcasts/<name>/<episode id>",
+            ],
+            matches: |url| host_matches(url, &["rss.com"]),
+            build: rss_dot_com,
+        },
+        Site {
+            name: "itunes",
+            patterns: &[
+                "https://podcasts.apple.com/<country>/podcast/<name>/id<id>",
+                "https://podcasts.apple.com/<country>/podcast/<name>/id<id>?i=<episode id>",
+            ],
+            matches: |url| host_matches(url, &["podcasts.apple.com"]),
+            build: itunes,
+        },
+    ]
+        .into_iter()
+        .chain(hosting::sites())
+        .chain(apps::sites())
+        .map(|site| Box::new(site) as Box<dyn Extractor>)
+        .collect()
+}
+
+/// Finds the rss link in the html of any page
+pub struct Generic;
+
+impl Extractor for Generic {
+    fn name(&self) -> &str {
+        "generic"
+    }
+
+    fn patterns(&self) -> &[&str] {
+        &["<any page with an rss link>", "<rss feed>"]
+    }
+
+    fn matches(&self, _url: &Url) -> bool {
+        true
+    }
+
+    fn build(&self, url: &Url) -> Result<Strategy, ParseError> {
+        Ok(Strategy::from_url(url.clone())
+            .try_op(UrlOperation::RssLink)
+            .rss()
+            .select_episode(EpisodeOperation::AudioLink { page: url.clone(), required: false }))
+    }
+}
+
+/// Itunes lookup api for a podcast id
+pub const ITUNES_LOOKUP: &str = "https://itunes.apple.com/lookup?id={}";
+
+/// Path to the feed url in the response from the itunes lookup api
+pub fn itunes_feed_url() -> Vec<&'static str> {
+    vec!["results", "feedUrl"]
+}
+
+/// Strategy for downloading the feed of the podcast with `itunes_id`
+pub fn itunes_lookup(itunes_id: &str) -> Result<Strategy, ParseError> {
+    Ok(Strategy::from_str(&ITUNES_LOOKUP.replace("{}", itunes_id))?
+        .json(itunes_feed_url())
+        .rss())
+}
+
+fn itunes(url: &Url) -> Result<Strategy, ParseError> {
+    let itunes_id = last_path_part(url)
+        .and_then(|part| part.strip_prefix("id"))
+        .ok_or(ParseError::MissingElement)?;
+    let strategy = itunes_lookup(itunes_id)?;
+    // Episode links have the episode id in the `i` parameter and the episode title before the id,
+    // like `/us/podcast/episode-title/id123?i=456`
+    let parts = path_parts(url);
+    let title_slug = parts.len().checked_sub(2)
+        .and_then(|i| parts.get(i))
+        .filter(|part| **part != "podcast")
+        .map(|part| part.to_string());
+    match url.query_pairs().find(|(key, _)| key == "i") {
+        Some((_, episode_id)) => Ok(strategy.select_episode(EpisodeOperation::Itunes {
+            podcast_id: itunes_id.to_string(),
+            episode_id: episode_id.into_owned(),
+            title_slug,
+        })),
+        None => Ok(strategy),
+    }
+}
+
+fn rss_dot_com(url: &Url) -> Result<Strategy, ParseError> {
+    let parts = path_parts(url);
+    let id = parts.get(1)
+        .ok_or(ParseError::MissingElement)?;
+    let strategy = Strategy::from_str(&format!("https://media.rss.com/{}/feed.xml", id))?
+        .rss();
+    if parts.len() > 2 {
+        Ok(strategy.select_episode(EpisodeOperation::AudioLink { page: url.clone(), required: true }))
+    } else {
+        Ok(strategy)
+    }
+}
@@ -0,0 +1,246 @@
+//! Extractors for podcast hosting platforms
+//!
+//! Feed urls are derived directly from the page url where the platform uses a predictable
+//! scheme. Otherwise the feed url is searched for in the page or looked up through an api.
+//! Episode pages select their episode from the audio link on the page.
+
+use crate::{
+    error::ParseError,
+    feed::strategy::{Strategy, EpisodeOperation},
+};
+use super::{extractors::Site, host_matches, domain_matches, subdomain, path_parts};
+use std::str::FromStr;
+use url::Url;
+
+pub fn sites() -> Vec<Site> {
+    vec![
+        Site {
+            name: "podbean",
+            patterns: &[
+                "https://<name>.podbean.com/",
+                "https://<name>.podbean.com/e/<episode>/",
+                "https://www.podbean.com/ew/<episode id>",
+            ],
+            matches: |url| domain_matches(url, "podbean.com")
+                && !host_matches(url, &["feed.podbean.com", "mcdn.podbean.com"]),
+            build: podbean,
+        },
+        Site {
+            name: "buzzsprout",
+            patterns: &[
+                "https://www.buzzsprout.com/<show id>",
+                "https://www.buzzsprout.com/<show id>/<episode id>",
+                "https://<name>.buzzsprout.com/",
+            ],
+            matches: |url| domain_matches(url, "buzzsprout.com")
+                && !host_matches(url, &["feeds.buzzsprout.com"]),
+            build: buzzsprout,
+        },
+        Site {
+            name: "libsyn",
+            patterns: &[
+                "https://<name>.libsyn.com/",
+                "https://<name>.libsyn.com/<episode>",
+            ],
+            matches: |url| subdomain(url, "libsyn.com")
+                .map(|name| !["traffic", "feeds", "html5-player"].contains(&name))
+                .unwrap_or(false),
+            build: libsyn,
+        },
+        Site {
+            name: "simplecast",
+            patterns: &[
+                "https://<name>.simplecast.com/",
+                "https://<name>.simplecast.com/episodes/<episode>",
+            ],
+            matches: |url| subdomain(url, "simplecast.com")
+                .map(|name| !["feeds", "api", "player", "cdn"].contains(&name))
+                .unwrap_or(false),
+            build: simplecast,
+        },
+        Site {
+            name: "acast",
+            patterns: &[
+                "https://shows.acast.com/<show>",
+                "https://shows.acast.com/<show>/episodes/<episode>",
+                "https://play.acast.com/s/<show>",
+                "https://embed.acast.com/<show>/<episode>",
+            ],
+            matches: |url| host_matches(url, &["shows.acast.com", "play.acast.com", "embed.acast.com"]),
+            build: acast,
+        },
+        Site {
+            name: "omny",
+            patterns: &[
+                "https://omny.fm/shows/<show>",
+                "https://omny.fm/shows/<show>/<episode>",
+            ],
+            matches: |url| host_matches(url, &["omny.fm", "www.omny.fm"])
+                && path_parts(url).first() == Some(&"shows"),
+            build: omny,
+        },
+        Site {
+            name: "transistor",
+            patterns: &[
+                "https://<name>.transistor.fm/",
+                "https://<name>.transistor.fm/episodes/<episode>",
+                "https://share.transistor.fm/s/<episode id>",
+            ],
+            matches: |url| subdomain(url, "transistor.fm")
+                .map(|name| !["feeds", "dashboard", "media"].contains(&name))
+                .unwrap_or(false),
+            build: transistor,
+        },
+        Site {
+            name: "captivate",
+            patterns: &[
+                "https://<name>.captivate.fm/",
+                "https://<name>.captivate.fm/episode/<episode>",
+                "https://player.captivate.fm/episode/<episode id>",
+            ],
+            matches: |url| subdomain(url, "captivate.fm")
+                .map(|name| !["feeds", "podcasts", "app"].contains(&name))
+                .unwrap_or(false),
+            build: captivate,
+        },
+        Site {
+            name: "spreaker",
+            patterns: &[
+                "https://www.spreaker.com/podcast/<show>--<show id>",
+                "https://www.spreaker.com/show/<show id or name>",
+                "https://www.spreaker.com/episode/<episode>--<episode id>",
+            ],
+            matches: |url| host_matches(url, &["www.spreaker.com", "spreaker.com"]),
+            build: spreaker,
+        },
+    ]
+}
+
+/// Strategy parsing `url` directly as an rss feed
+fn feed(url: &str) -> Result<Strategy, ParseError> {
+    Ok(Strategy::from_str(url)?.rss())
+}
+
+/// Selects the episode on the page at `url` if `is_episode` is set
+fn episode_page(strategy: Strategy, url: &Url, is_episode: bool) -> Strategy {
+    match is_episode {
+        true => strategy.select_episode(EpisodeOperation::AudioLink { page: url.clone(), required: true }),
+        false => strategy,
+    }
+}
+
+/// Strategy finding a feed url matching `pattern` in the content of `url`
+fn find_in_page(url: &Url, pattern: &'static str) -> Result<Strategy, ParseError> {
+    Ok(Strategy::from_url(url.clone())
+        .find(pattern)
+        .rss())
+}
+
+fn podbean(url: &Url) -> Result<Strategy, ParseError> {
+    let first = path_parts(url).first().copied();
+    let strategy = match subdomain(url, "podbean.com") {
+        Some(name) => feed(&format!("https://feed.podbean.com/{}/feed.xml", name))?,
+        None => find_in_page(url, r"https://feed\.podbean\.com/[\w.-]+/feed\.xml")?,
+    };
+    Ok(episode_page(strategy, url, matches!(first, Some("e" | "ew"))))
+}
+
+fn buzzsprout(url: &Url) -> Result<Strategy, ParseError> {
+    let show_id = path_parts(url).first()
+        .filter(|part| part.chars().all(|c| c.is_ascii_digit()))
+        .copied();
+    let strategy = match (subdomain(url, "buzzsprout.com"), show_id) {
+        (None, Some(show_id)) => feed(&format!("https://feeds.buzzsprout.com/{}.rss", show_id))?,
+        _ => find_in_page(url, r"https://feeds\.buzzsprout\.com/\d+\.rss")?,
+    };
+    // Episodes are below the show id, like `/123456/7890123`
+    Ok(episode_page(strategy, url, path_parts(url).len() > 1))
+}
+
+fn libsyn(url: &Url) -> Result<Strategy, ParseError> {
+    let name = subdomain(url, "libsyn.com")
+        .ok_or(ParseError::MissingElement)?;
+    let strategy = feed(&format!("https://{}.libsyn.com/rss", name))?;
+    // Episodes are directly below the root, next to the feed and episode list pages
+    let is_episode = path_parts(url).first()
+        .is_some_and(|part| !["rss", "page", "website"].contains(part));
+    Ok(episode_page(strategy, url, is_episode))
+}
+
+fn simplecast(url: &Url) -> Result<Strategy, ParseError> {
+    let strategy = find_in_page(url, r"https://feeds\.simplecast\.com/[\w-]+")?;
+    let is_episode = matches!(path_parts(url).as_slice(), ["episodes", _, ..]);
+    Ok(episode_page(strategy, url, is_episode))
+}
+
+fn acast(url: &Url) -> Result<Strategy, ParseError> {
+    let parts = path_parts(url);
+    let show = match url.host_str() {
+        Some("play.acast.com") => parts.get(1),
+        _ => parts.first(),
+    }.ok_or(ParseError::MissingElement)?;
+    let strategy = feed(&format!("https://feeds.acast.com/public/shows/{}", show))?;
+    let is_episode = match url.host_str() {
+        Some("embed.acast.com") => parts.len() > 1,
+        Some("play.acast.com") => parts.len() > 2,
+        _ => matches!(parts.as_slice(), [_, "episodes", _, ..]),
+    };
+    Ok(episode_page(strategy, url, is_episode))
+}
+
+fn omny(url: &Url) -> Result<Strategy, ParseError> {
+    let parts = path_parts(url);
+    let show = parts.get(1)
+        .ok_or(ParseError::MissingElement)?;
+    let strategy = feed(&format!("https://omny.fm/shows/{}/playlists/podcast.rss", show))?;
+    let is_episode = parts.get(2).is_some_and(|part| *part != "playlists");
+    Ok(episode_page(strategy, url, is_episode))
+}
+
+fn transistor(url: &Url) -> Result<Strategy, ParseError> {
+    let strategy = match subdomain(url, "transistor.fm") {
+        Some("share") => find_in_page(url, r"https://feeds\.transistor\.fm/[\w-]+")?,
+        Some(name) => feed(&format!("https://feeds.transistor.fm/{}", name))?,
+        None => return Err(ParseError::MissingElement),
+    };
+    let is_episode = matches!(path_parts(url).as_slice(), ["episodes" | "s", _, ..]);
+    Ok(episode_page(strategy, url, is_episode))
+}
+
+fn captivate(url: &Url) -> Result<Strategy, ParseError> {
+    let strategy = match subdomain(url, "captivate.fm") {
+        Some("player") => find_in_page(url, r"https://feeds\.captivate\.fm/[\w-]+/")?,
+        Some(name) => feed(&format!("https://feeds.captivate.fm/{}/", name))?,
+        None => return Err(ParseError::MissingElement),
+    };
+    let is_episode = matches!(path_parts(url).as_slice(), ["episode", _, ..]);
+    Ok(episode_page(strategy, url, is_episode))
+}
+
+const SPREAKER_FEED: &str = "https://www.spreaker.com/show/{}/episodes/feed";
+
+fn spreaker(url: &Url) -> Result<Strategy, ParseError> {
+    let parts = path_parts(url);
+    let (kind, name) = match parts.as_slice() {
+        [kind, name, ..] => (*kind, *name),
+        _ => return Err(ParseError::MissingElement),
+    };
+    // Newer urls end with `--<id>`
+    let id = name.rsplit("--").next().unwrap_or(name);
+    let is_id = id.chars().all(|c| c.is_ascii_digit());
+    match kind {
+        "podcast" | "show" if is_id => feed(&SPREAKER_FEED.replace("{}", id)),
+        "podcast" | "show" => {
+            Ok(Strategy::from_str(&format!("https://api.spreaker.com/v2/shows/{}", id))?
+                .json_format(vec!["response", "show", "show_id"], SPREAKER_FEED)
+                .rss())
+        },
+        "episode" => {
+            let strategy = Strategy::from_str(&format!("https://api.spreaker.com/v2/episodes/{}", id))?
+                .json_format(vec!["response", "episode", "show_id"], SPREAKER_FEED)
+                .rss();
+            Ok(episode_page(strategy, url, true))
+        },
+        _ => Err(ParseError::MissingElement),
+    }
+}
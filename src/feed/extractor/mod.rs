@@ -0,0 +1,109 @@
+mod apps;
+mod extractors;
+mod hosting;
+
+use crate::error::ParseError;
+use super::strategy::Strategy;
+use url::Url;
+
+/// Turns urls from a specific site into a strategy for downloading the podcast feed
+pub trait Extractor: Send + Sync {
+    /// Name of extractor
+    fn name(&self) -> &str;
+
+    /// Human readable url patterns handled by the extractor
+    fn patterns(&self) -> &[&str];
+
+    /// Returns true if the extractor can handle `url`
+    fn matches(&self, url: &Url) -> bool;
+
+    /// Creates strategy for downloading the podcast feed of `url`
+    fn build(&self, url: &Url) -> Result<Strategy, ParseError>;
+}
+
+/// Ordered collection of extractors
+///
+/// Extractors are tried in order and the first one matching the url is used. The generic
+/// extractor looking for an rss link in the page matches everything and is always tried last.
+pub struct ExtractorRegistry {
+    extractors: Vec<Box<dyn Extractor>>,
+    fallback: Box<dyn Extractor>,
+}
+
+impl ExtractorRegistry {
+    /// Creates a registry without any site specific extractors
+    pub fn empty() -> Self {
+        Self {
+            extractors: Vec::new(),
+            fallback: Box::new(extractors::Generic),
+        }
+    }
+
+    /// Registers an extractor. Extractors registered later take priority over earlier ones,
+    /// including the built-in ones.
+    pub fn register<E: Extractor + 'static>(&mut self, extractor: E) -> &mut Self {
+        self.extractors.insert(0, Box::new(extractor));
+        self
+    }
+
+    /// Iterates over all extractors in the order they are tried
+    pub fn extractors(&self) -> impl Iterator<Item = &dyn Extractor> {
+        self.extractors.iter()
+            .chain(std::iter::once(&self.fallback))
+            .map(|extractor| extractor.as_ref())
+    }
+
+    /// Creates strategy for downloading the podcast feed of `input`
+    pub fn create_strategy(&self, input: &str) -> Result<Strategy, ParseError> {
+        let url = Url::parse(input)?;
+        let extractor = self.extractors.iter()
+            .find(|extractor| extractor.matches(&url))
+            .unwrap_or(&self.fallback);
+        log::debug!("Using extractor: {}", extractor.name());
+        extractor.build(&url)
+    }
+}
+
+impl Default for ExtractorRegistry {
+    /// Creates a registry with all built-in extractors
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.extractors = extractors::builtin();
+        registry
+    }
+}
+
+/// Returns true if the host of `url` is one of `hosts`
+pub fn host_matches(url: &Url, hosts: &[&str]) -> bool {
+    url.host_str()
+        .map(|host| hosts.contains(&host))
+        .unwrap_or(false)
+}
+
+/// Returns the last non-empty segment of the path in `url`
+pub fn last_path_part(url: &Url) -> Option<&str> {
+    url.path_segments()?
+        .rfind(|x| !x.is_empty())
+}
+
+/// Returns true if the host of `url` is `domain` or one of its subdomains
+pub fn domain_matches(url: &Url, domain: &str) -> bool {
+    url.host_str()
+        .map(|host| host == domain || host.ends_with(&format!(".{}", domain)))
+        .unwrap_or(false)
+}
+
+/// Returns the subdomain of `url` directly under `domain`, ignoring `www`
+pub fn subdomain<'a>(url: &'a Url, domain: &str) -> Option<&'a str> {
+    url.host_str()?
+        .strip_suffix(domain)?
+        .strip_suffix('.')
+        .filter(|name| !name.is_empty() && *name != "www" && !name.contains('.'))
+}
+
+/// Returns the non-empty segments of the path in `url`
+pub fn path_parts(url: &Url) -> Vec<&str> {
+    url.path_segments()
+        .map(|segments| segments.filter(|x| !x.is_empty()).collect())
+        .unwrap_or_default()
+}
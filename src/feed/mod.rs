@@ -1,31 +1,162 @@
+mod extractor;
 mod parse;
 mod strategy;
 
+pub use extractor::{Extractor, ExtractorRegistry, host_matches, last_path_part};
+pub use strategy::{Strategy, UrlOperation, EpisodeOperation, Fetch, execute_strategy};
+
 use crate::error::Error;
 use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 
 /// Podcast feed
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Podcast {
     /// Title of podcast
     pub title: String,
     /// Podcast episodes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub episodes: Vec<Episode>,
     /// Description of podcast
     pub description: Option<String>,
+    /// Author of podcast
+    pub author: Option<String>,
     /// Link to cover image
     pub image: Option<String>,
+    /// Link to podcast website
+    pub link: Option<String>,
+    /// Language code of podcast, like `en-us`
+    pub language: Option<String>,
+    /// Categories of podcast
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Podcasting 2.0 guid of podcast
+    pub guid: Option<String>,
+    /// Url of the rss feed
+    pub feed_url: Option<String>,
+    /// Index in `episodes` of the episode the input url pointed to
+    #[serde(skip)]
+    pub selected_episode: Option<usize>,
+}
+
+impl Podcast {
+
+    /// Returns a copy of the podcast metadata without episodes
+    pub fn without_episodes(&self) -> Podcast {
+        Podcast {
+            title: self.title.clone(),
+            episodes: Vec::new(),
+            description: self.description.clone(),
+            author: self.author.clone(),
+            image: self.image.clone(),
+            link: self.link.clone(),
+            language: self.language.clone(),
+            categories: self.categories.clone(),
+            guid: self.guid.clone(),
+            feed_url: self.feed_url.clone(),
+            selected_episode: None,
+        }
+    }
+
 }
 
 /// Podcast episode
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Episode {
+    /// Title of episode
     pub title: String,
+    /// Link to episode audio
     pub link: String,
+    /// Size of episode audio in bytes according to the feed
+    pub length: Option<u64>,
+    /// Mime type of episode audio according to the feed
+    pub content_type: Option<String>,
+    /// Duration of episode in seconds
+    pub duration: Option<u64>,
+    /// Globally unique identifier of episode
+    pub guid: Option<String>,
+    /// Publication date
     pub pub_date: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// Position of episode in feed, starting from the oldest at 1
     pub index: Option<usize>,
+    /// Episode number according to the feed
+    pub episode_number: Option<usize>,
+    /// Season number according to the feed
+    pub season: Option<usize>,
+    /// Author of episode
     pub author: Option<String>,
+    /// Description of episode
     pub description: Option<String>,
+    /// Type of episode according to the feed
+    pub episode_type: Option<EpisodeType>,
+    /// Whether episode contains explicit content according to the feed
+    pub explicit: Option<bool>,
+    /// Link to episode image
+    pub image: Option<String>,
+    /// Link to Podcasting 2.0 chapters
+    pub chapters_url: Option<String>,
+    /// Podcasting 2.0 transcripts
+    #[serde(default)]
+    pub transcripts: Vec<Transcript>,
+}
+
+/// Link to a transcript of an episode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub url: String,
+    /// Mime type, like `text/vtt`
+    pub content_type: Option<String>,
+    /// Language code of transcript
+    pub language: Option<String>,
+}
+
+/// Type of episode from itunes:episodeType
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpisodeType {
+    /// Regular episode
+    Full,
+    /// Short promotional episode
+    Trailer,
+    /// Extra content like behind the scenes
+    Bonus,
+}
+
+impl EpisodeType {
+    /// Names accepted by `from_str`
+    pub const VARIANTS: &'static [&'static str] = &["full", "trailer", "bonus"];
+}
+
+impl FromStr for EpisodeType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "full" => Ok(Self::Full),
+            "trailer" => Ok(Self::Trailer),
+            "bonus" => Ok(Self::Bonus),
+            _ => Err(format!("Unknown episode type: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for EpisodeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Full => "full",
+            Self::Trailer => "trailer",
+            Self::Bonus => "bonus",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Parses a duration given as seconds, `MM:SS` or `HH:MM:SS`
+pub fn parse_duration(duration: &str) -> Option<u64> {
+    duration.trim()
+        .split(':')
+        .try_fold(0, |total, part| Some(total * 60 + part.trim().parse::<f64>().ok()? as u64))
 }
 
 impl Episode {
@@ -48,10 +179,40 @@ impl Episode {
             .map(|x| x.year() as usize)
     }
 
-}
+    /// Return file extension of episode audio from its link or mime type
+    pub fn extension(&self) -> Option<String> {
+        let from_link = url::Url::parse(&self.link).ok()
+            .and_then(|url| {
+                let (_, extension) = url.path().rsplit_once('/')?.1.rsplit_once('.')?;
+                Some(extension.to_ascii_lowercase())
+            })
+            .filter(|x| (1..=5).contains(&x.len()) && x.chars().all(|c| c.is_ascii_alphanumeric()));
+        let from_type = || {
+            let extension = match self.content_type.as_deref()? {
+                "audio/mpeg" | "audio/mp3" => "mp3",
+                "audio/mp4" | "audio/x-m4a" | "audio/m4a" | "audio/aac" => "m4a",
+                "video/mp4" => "mp4",
+                "video/quicktime" => "mov",
+                "audio/ogg" => "ogg",
+                "audio/opus" => "opus",
+                "audio/flac" | "audio/x-flac" => "flac",
+                "audio/wav" | "audio/x-wav" => "wav",
+                _ => return None,
+            };
+            Some(extension.to_string())
+        };
+        from_link.or_else(from_type)
+    }
 
+}
 
+/// Resolves `url` to a podcast feed using the built-in extractors and downloads it
 pub async fn download_feed(url: &str) -> Result<Podcast, Error> {
-    let strategy = strategy::create_strategy(url)?;
-    strategy::execute_strategy(&strategy).await
+    download_feed_with(url, &ExtractorRegistry::default()).await
+}
+
+/// Resolves `url` to a podcast feed using `extractors` and downloads it
+pub async fn download_feed_with(url: &str, extractors: &ExtractorRegistry) -> Result<Podcast, Error> {
+    let strategy = extractors.create_strategy(url)?;
+    execute_strategy(&strategy, &reqwest::Client::new()).await
 }
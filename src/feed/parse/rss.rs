@@ -1,4 +1,6 @@
-use crate::{Podcast, Episode};
+use crate::{Podcast, Episode, feed::Transcript};
+use rss::extension::{Extension, ExtensionMap};
+use crate::feed::parse_duration;
 use crate::error::ParseError;
 
 pub fn parse_rss_feed(content: &bytes::Bytes) -> Result<Podcast, ParseError> {
@@ -7,22 +9,107 @@ pub fn parse_rss_feed(content: &bytes::Bytes) -> Result<Podcast, ParseError> {
         title: channel.title.clone(),
         episodes: extract_episodes(&channel)?,
         description: Some(channel.description.clone()),
-        image: channel.image.map(|x| x.url),
+        author: channel.itunes_ext.as_ref()
+            .and_then(|x| x.author.clone()),
+        image: channel.image.as_ref().map(|x| x.url.clone()),
+        link: Some(channel.link.clone()).filter(|x| !x.is_empty()),
+        language: channel.language.clone(),
+        categories: categories(&channel),
+        guid: podcast_extension(&channel.extensions, "guid")
+            .and_then(|x| x.value.clone()),
+        feed_url: None,
+        selected_episode: None,
     })
 }
 
 fn extract_episodes(channel: &rss::Channel) -> Result<Vec<Episode>, ParseError> {
+    let podcast_explicit = channel.itunes_ext.as_ref()
+        .and_then(|x| x.explicit.as_deref())
+        .and_then(parse_explicit);
     channel.items.iter()
         .rev()
         .enumerate()
         .map(|(index, item)| Ok(Episode {
             title: item.title.clone().unwrap(),
             link: item.enclosure.clone().unwrap().url,
+            length: item.enclosure.as_ref()
+                .and_then(|x| x.length.trim().parse().ok()),
+            content_type: item.enclosure.as_ref()
+                .map(|x| x.mime_type.clone())
+                .filter(|x| !x.is_empty()),
+            duration: item.itunes_ext.as_ref()
+                .and_then(|x| x.duration.as_deref())
+                .and_then(parse_duration),
+            guid: item.guid.as_ref().map(|x| x.value.clone()),
             pub_date: item.pub_date.as_ref()
-                .and_then(|x| chrono::DateTime::parse_from_rfc2822(&x).ok()),
+                .and_then(|x| chrono::DateTime::parse_from_rfc2822(x).ok()),
             index: Some(index+1),
+            episode_number: item.itunes_ext.as_ref()
+                .and_then(|x| x.episode.as_ref())
+                .and_then(|x| x.trim().parse().ok()),
+            season: item.itunes_ext.as_ref()
+                .and_then(|x| x.season.as_ref())
+                .and_then(|x| x.trim().parse().ok()),
             author: item.author.clone(),
             description: item.description.clone(),
+            episode_type: item.itunes_ext.as_ref()
+                .and_then(|x| x.episode_type.as_ref())
+                .and_then(|x| x.parse().ok()),
+            explicit: item.itunes_ext.as_ref()
+                .and_then(|x| x.explicit.as_deref())
+                .and_then(parse_explicit)
+                .or(podcast_explicit),
+            image: item.itunes_ext.as_ref()
+                .and_then(|x| x.image.clone()),
+            chapters_url: podcast_extension(&item.extensions, "chapters")
+                .and_then(|x| x.attrs.get("url").cloned()),
+            transcripts: podcast_extensions(&item.extensions, "transcript")
+                .filter_map(|x| Some(Transcript {
+                    url: x.attrs.get("url")?.clone(),
+                    content_type: x.attrs.get("type").cloned(),
+                    language: x.attrs.get("language").cloned(),
+                }))
+                .collect(),
         }))
         .collect()
 }
+
+
+/// Parses itunes:explicit, which is `true`/`false` or the older `yes`/`no`/`clean`
+fn parse_explicit(explicit: &str) -> Option<bool> {
+    match explicit.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "explicit" => Some(true),
+        "false" | "no" | "clean" => Some(false),
+        _ => None,
+    }
+}
+
+/// Categories from itunes:category, or the rss categories if there are none
+fn categories(channel: &rss::Channel) -> Vec<String> {
+    let itunes: Vec<String> = channel.itunes_ext.iter()
+        .flat_map(|x| &x.categories)
+        .flat_map(|category| {
+            std::iter::once(category.text.clone())
+                .chain(category.subcategory.iter().map(|x| x.text.clone()))
+        })
+        .collect();
+    if !itunes.is_empty() {
+        return itunes;
+    }
+    channel.categories.iter()
+        .map(|x| x.name.clone())
+        .collect()
+}
+
+/// All Podcasting 2.0 elements called `name`
+fn podcast_extensions<'a>(extensions: &'a ExtensionMap, name: &str) -> impl Iterator<Item = &'a Extension> {
+    extensions.get("podcast")
+        .and_then(|elements| elements.get(name))
+        .into_iter()
+        .flatten()
+}
+
+/// First Podcasting 2.0 element called `name`
+fn podcast_extension<'a>(extensions: &'a ExtensionMap, name: &str) -> Option<&'a Extension> {
+    podcast_extensions(extensions, name).next()
+}
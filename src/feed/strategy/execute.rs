@@ -2,52 +2,221 @@ use crate::{
     error::{Error, ParseError},
     Podcast,
     feed::{
-        strategy::{Strategy, Operation, PodcastOperation, UrlOperation},
+        strategy::{Strategy, Operation, PodcastOperation, UrlOperation, EpisodeOperation},
         parse::parse_rss_feed,
     },
 };
+use std::future::Future;
+use bytes::Bytes;
+use reqwest::Client;
 use url::Url;
 
-pub async fn execute_strategy(strategy: &Strategy) -> Result<Podcast, Error> {
+/// Most episodes returned by the itunes lookup api
+const ITUNES_EPISODE_LIMIT: usize = 200;
+
+/// Source of the content behind urls visited by a strategy
+pub trait Fetch {
+    /// Downloads content of `url`
+    fn fetch(&self, url: &Url) -> impl Future<Output = Result<Bytes, Error>> + Send;
+}
+
+impl Fetch for Client {
+    fn fetch(&self, url: &Url) -> impl Future<Output = Result<Bytes, Error>> + Send {
+        let request = self.get(url.clone());
+        async move {
+            Ok(request.send().await?.bytes().await?)
+        }
+    }
+}
+
+pub async fn execute_strategy<F: Fetch>(strategy: &Strategy, fetcher: &F) -> Result<Podcast, Error> {
     let mut url = strategy.url.clone();
+    let mut first_content = None;
     for operation in &strategy.operations {
-        let content = reqwest::get(url.clone())
-            .await?
-            .bytes()
-            .await?;
+        let content = fetcher.fetch(&url).await?;
+        if first_content.is_none() {
+            first_content = Some(content.clone());
+        }
         match operation {
-            Operation::Podcast(op) => return Ok(execute_podcast_operation(&op, &content)?),
+            Operation::Podcast(op) => {
+                let mut podcast = execute_podcast_operation(op, &content)?;
+                podcast.feed_url = Some(url.to_string());
+                if let Some(episode_op) = &strategy.episode {
+                    // Avoid downloading the input page twice
+                    let cached = first_content.filter(|_| url_of(episode_op) == Some(&strategy.url));
+                    podcast.selected_episode = execute_episode_operation(episode_op, &podcast, fetcher, cached).await?;
+                }
+                return Ok(podcast);
+            },
             Operation::Url(op) => {
-                url = execute_url_operation(&op, &content)?;
+                url = execute_url_operation(op, &content)?;
             },
             Operation::Try(op) => {
                 log::debug!("Using try operation: {:?}", op);
-                match execute_url_operation(&op, &content) {
+                match execute_url_operation(op, &content) {
                     Ok(x) => url = x,
                     Err(_) => log::debug!("Try operation did not succeed"),
                 }
             }
         }
     }
-    unreachable!()
+    // Only possible with strategies from custom extractors
+    Err(Error::IncompleteStrategy(strategy.url.to_string()))
 }
 
-fn execute_podcast_operation(op: &PodcastOperation, content: &bytes::Bytes) -> Result<Podcast, ParseError> {
+/// Url of the page used by `op`, if it uses one
+fn url_of(op: &EpisodeOperation) -> Option<&Url> {
     match op {
-        PodcastOperation::RSS => parse_rss_feed(content)
+        EpisodeOperation::AudioLink { page, .. } => Some(page),
+        EpisodeOperation::Itunes { .. } => None,
     }
 }
 
-fn execute_url_operation(op: &UrlOperation, content: &bytes::Bytes) -> Result<Url, ParseError> {
+/// Finds the index of the episode selected by `op` in `podcast`
+async fn execute_episode_operation<F: Fetch>(
+    op: &EpisodeOperation,
+    podcast: &Podcast,
+    fetcher: &F,
+    cached: Option<Bytes>,
+) -> Result<Option<usize>, Error> {
+    log::debug!("Executing episode operation: {:?}", op);
+    match op {
+        EpisodeOperation::AudioLink { page, required } => {
+            let is_show_page = podcast.link.as_deref()
+                .is_some_and(|link| same_page(link, page.as_str()));
+            if is_show_page && !required {
+                return Ok(None);
+            }
+            let content = match cached {
+                Some(content) => content,
+                None => fetcher.fetch(page).await?,
+            };
+            let index = find_audio_link(&content, *required)
+                .and_then(|link| find_episode(podcast, None, Some(&link)));
+            match index {
+                None if *required => Err(Error::EpisodeNotFound(page.to_string())),
+                _ => Ok(index),
+            }
+        },
+        EpisodeOperation::Itunes { podcast_id, episode_id, title_slug } => {
+            let lookup = Url::parse(&format!(
+                "https://itunes.apple.com/lookup?id={}&entity=podcastEpisode&limit={}",
+                podcast_id, ITUNES_EPISODE_LIMIT,
+            )).map_err(ParseError::from)?;
+            let content = fetcher.fetch(&lookup).await?;
+            let text = std::str::from_utf8(&content).map_err(ParseError::from)?;
+            let json: serde_json::Value = serde_json::from_str(text).map_err(ParseError::from)?;
+            let index = json["results"].as_array()
+                .and_then(|results| results.iter().find(|result| {
+                    result["trackId"].as_u64().map(|x| x.to_string()).as_ref() == Some(episode_id)
+                }))
+                .and_then(|result| find_episode(podcast, result["episodeGuid"].as_str(), result["episodeUrl"].as_str()));
+            // Older episodes are missing from the lookup
+            let index = index.or_else(|| {
+                let slug = title_slug.as_deref()?;
+                log::debug!("Episode {} is not in the itunes lookup, matching title {}", episode_id, slug);
+                podcast.episodes.iter().position(|episode| title_slug_of(&episode.title) == slug)
+            });
+            index.map(Some).ok_or_else(|| Error::EpisodeNotFound(format!(
+                "itunes episode {} (the itunes api only lists the newest {} episodes and no title matched the url)",
+                episode_id, ITUNES_EPISODE_LIMIT,
+            )))
+        },
+    }
+}
+
+/// Slug of an episode title as used in itunes urls, like `episode-2-second`
+fn title_slug_of(title: &str) -> String {
+    title.to_lowercase()
+        .replace(['\'', '\u{2019}'], "")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Finds audio link of the episode on an html page
+///
+/// `<audio>` players are only used if `players` is set, since show pages often have one for the
+/// latest episode.
+fn find_audio_link(content: &Bytes, players: bool) -> Option<String> {
+    let text = std::str::from_utf8(content).ok()?;
+    let doc = scraper::Html::parse_document(text);
+    let meta = [
+        (r#"meta[property="og:audio"]"#, "content"),
+        (r#"meta[property="og:audio:url"]"#, "content"),
+        (r#"meta[property="og:audio:secure_url"]"#, "content"),
+    ];
+    let player = [
+        ("audio[src]", "src"),
+        ("audio source[src]", "src"),
+    ];
+    let player: &[_] = if players { &player } else { &[] };
+    meta.iter().chain(player).find_map(|(selector, attr)| {
+        let selector = scraper::Selector::parse(selector).unwrap();
+        doc.select(&selector)
+            .find_map(|element| element.value().attr(attr).map(String::from))
+    })
+}
+
+/// Finds index of episode in `podcast` with a matching guid or audio link
+fn find_episode(podcast: &Podcast, guid: Option<&str>, link: Option<&str>) -> Option<usize> {
+    if let Some(guid) = guid {
+        let index = podcast.episodes.iter()
+            .position(|episode| episode.guid.as_deref() == Some(guid));
+        if index.is_some() {
+            return index;
+        }
+    }
+    let link = normalize_link(link?);
+    if link.is_empty() {
+        return None;
+    }
+    podcast.episodes.iter()
+        .position(|episode| {
+            let episode_link = normalize_link(&episode.link);
+            // Feeds often wrap links in tracking redirects like `https://tracker/https://host/file`
+            episode_link.ends_with(&link) || link.ends_with(&episode_link)
+        })
+}
+
+/// Checks if two urls point to the same page, ignoring scheme, query and trailing slashes
+fn same_page(a: &str, b: &str) -> bool {
+    normalize_link(a).trim_end_matches('/') == normalize_link(b).trim_end_matches('/')
+}
+
+/// Removes scheme, query and fragment from link
+fn normalize_link(link: &str) -> String {
+    let link = link.split(['?', '#']).next().unwrap_or(link);
+    link.split_once("://")
+        .map(|(_, rest)| rest)
+        .unwrap_or(link)
+        .to_string()
+}
+
+fn execute_podcast_operation(op: &PodcastOperation, content: &Bytes) -> Result<Podcast, ParseError> {
+    match op {
+        PodcastOperation::Rss => parse_rss_feed(content)
+    }
+}
+
+fn execute_url_operation(op: &UrlOperation, content: &Bytes) -> Result<Url, ParseError> {
     log::debug!("Executing url operation: {:?}", op);
     let url = match op {
         UrlOperation::Json(lookup) => execute_url_json(content, lookup)?,
+        UrlOperation::JsonFormat(lookup, template) => {
+            template.replace("{}", &execute_url_json(content, lookup)?)
+        },
+        UrlOperation::Find(pattern) => execute_url_find(content, pattern)?,
+        UrlOperation::FindFormat(pattern, template) => {
+            template.replace("{}", &execute_url_find(content, pattern)?)
+        },
         UrlOperation::RssLink => execute_url_rsslink(content)?,
     };
     Ok(Url::parse(&url)?)
 }
 
-fn execute_url_json(content: &bytes::Bytes, lookup: &Vec<&str>) -> Result<String, ParseError> {
+fn execute_url_json(content: &Bytes, lookup: &[&str]) -> Result<String, ParseError> {
     let text = std::str::from_utf8(content)?;
     let mut json: serde_json::Value = serde_json::from_str(text)?;
     let mut iter = lookup.iter();
@@ -55,17 +224,34 @@ fn execute_url_json(content: &bytes::Bytes, lookup: &Vec<&str>) -> Result<String
         if json.is_array() {
             json = json[0].take();
         } else if json.is_object() {
-            json = json[iter.next().unwrap()].take();
+            let key = iter.next().ok_or(ParseError::MissingElement)?;
+            json = json[key].take();
         } else {
             break;
         }
     }
-    Ok(json.as_str().unwrap().to_string())
+    match json {
+        serde_json::Value::String(value) => Ok(value),
+        serde_json::Value::Number(value) => Ok(value.to_string()),
+        _ => Err(ParseError::MissingElement),
+    }
+}
+
+fn execute_url_find(content: &Bytes, pattern: &str) -> Result<String, ParseError> {
+    let text = std::str::from_utf8(content)?;
+    let regex = regex::Regex::new(pattern)?;
+    let captures = regex.captures(text)
+        .ok_or(ParseError::MissingElement)?;
+    let result = captures.get(1)
+        .or_else(|| captures.get(0))
+        .ok_or(ParseError::MissingElement)?;
+    // Urls embedded in json often have escaped slashes
+    Ok(result.as_str().replace("\\/", "/"))
 }
 
-fn execute_url_rsslink(content: &bytes::Bytes) -> Result<String, ParseError> {
+fn execute_url_rsslink(content: &Bytes) -> Result<String, ParseError> {
     let text = std::str::from_utf8(content)?;
-    let doc = scraper::Html::parse_document(&text);
+    let doc = scraper::Html::parse_document(text);
     let selector = scraper::Selector::parse(r#"link[type="application/rss+xml"]"#)
         .unwrap();
     let result = doc.select(&selector)
@@ -1,28 +1,28 @@
-mod create;
 mod execute;
 
+use crate::error::ParseError;
+use std::str::FromStr;
 use url::Url;
 
-pub use create::create_strategy;
-pub use execute::execute_strategy;
+pub use execute::{execute_strategy, Fetch};
 
+/// Steps for getting from an url to a podcast feed
+///
+/// Every operation downloads the current url and either replaces it with a new url found in the
+/// content or parses the content as a podcast.
 pub struct Strategy {
     url: Url,
     operations: Vec<Operation>,
+    episode: Option<EpisodeOperation>,
 }
 
 impl Strategy {
-    fn from_url(url: Url) -> Self {
+    /// Creates an empty strategy starting at `url`
+    pub fn from_url(url: Url) -> Self {
         Self {
             url,
             operations: vec![],
-        }
-    }
-
-    fn from_str(url: &str) -> Self {
-        Self {
-            url: Url::parse(url).unwrap(),
-            operations: Vec::new(),
+            episode: None,
         }
     }
 
@@ -40,15 +40,41 @@ impl Strategy {
         self
     }
 
-    fn rss(self) -> Self {
-        self.add_podcast_operation(PodcastOperation::RSS)
+    /// Parse content as rss feed
+    pub fn rss(self) -> Self {
+        self.add_podcast_operation(PodcastOperation::Rss)
     }
 
-    fn json(self, lookup: Vec<&'static str>) -> Self {
+    /// Select url in json by following `lookup`
+    pub fn json(self, lookup: Vec<&'static str>) -> Self {
         self.add_url_operation(UrlOperation::Json(lookup))
     }
 
-    fn try_op(mut self, op: UrlOperation) -> Self {
+    /// Select value in json by following `lookup` and insert it in place of `{}` in `template`
+    pub fn json_format(self, lookup: Vec<&'static str>, template: &'static str) -> Self {
+        self.add_url_operation(UrlOperation::JsonFormat(lookup, template))
+    }
+
+    /// Select first match of regex `pattern` in content. Uses the first capture group if the
+    /// pattern has one.
+    pub fn find(self, pattern: &'static str) -> Self {
+        self.add_url_operation(UrlOperation::Find(pattern))
+    }
+
+    /// Select first match of regex `pattern` in content and insert it in place of `{}` in
+    /// `template`
+    pub fn find_format(self, pattern: &'static str, template: &'static str) -> Self {
+        self.add_url_operation(UrlOperation::FindFormat(pattern, template))
+    }
+
+    /// Select a single episode from the podcast with `op`
+    pub fn select_episode(mut self, op: EpisodeOperation) -> Self {
+        self.episode = Some(op);
+        self
+    }
+
+    /// Apply `op` if it succeeds and skip it otherwise
+    pub fn try_op(mut self, op: UrlOperation) -> Self {
         self.operations.push(
             Operation::Try(op)
         );
@@ -58,6 +84,19 @@ impl Strategy {
 
 }
 
+impl FromStr for Strategy {
+    type Err = ParseError;
+
+    /// Creates an empty strategy starting at `url`
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            url: Url::parse(url)?,
+            operations: Vec::new(),
+            episode: None,
+        })
+    }
+}
+
 enum Operation {
     Podcast(PodcastOperation),
     Url(UrlOperation),
@@ -67,14 +106,42 @@ enum Operation {
 
 /// Operation that returns a podcast object
 enum PodcastOperation {
-    RSS,
+    Rss,
 }
 
 /// Operation that returns an url
 #[derive(Debug)]
-enum UrlOperation {
+pub enum UrlOperation {
     /// Select url in json
     Json(Vec<&'static str>),
+    /// Select value in json and format it into an url
+    JsonFormat(Vec<&'static str>, &'static str),
+    /// Find first match of regex in content
+    Find(&'static str),
+    /// Find first match of regex in content and format it into an url
+    FindFormat(&'static str, &'static str),
     /// Find rss link in html
     RssLink,
 }
+
+/// Operation that finds a single episode in the podcast
+#[derive(Debug)]
+pub enum EpisodeOperation {
+    /// Find audio link in html page. Fails if no episode matches and `required` is set
+    ///
+    /// Without `required`, the page may be the show page. Those often have a player for the latest
+    /// episode, so only `og:audio` meta tags are used and the channel link is never an episode.
+    AudioLink {
+        page: Url,
+        required: bool,
+    },
+    /// Look up episode in the itunes api
+    ///
+    /// The api only lists the newest 200 episodes. Older episodes are matched by `title_slug`, the
+    /// episode title in the url.
+    Itunes {
+        podcast_id: String,
+        episode_id: String,
+        title_slug: Option<String>,
+    },
+}
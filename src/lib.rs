@@ -0,0 +1,42 @@
+//! Library for resolving and downloading podcast feeds
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), podcast_dl::Error> {
+//! let podcast = podcast_dl::download_feed("https://podcasts.apple.com/us/podcast/id1234").await?;
+//! let options = podcast_dl::WriteOptions {
+//!     template: "{podcast_title}/{episode_title}.mp3".to_string(),
+//!     limit: Some(1),
+//!     ..Default::default()
+//! };
+//! podcast_dl::download_podcast(&podcast, &options).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The library reports progress through the `log` crate and never sets up a logger itself.
+
+/// Error types
+pub mod error;
+/// Resolving and parsing podcast feeds
+pub mod feed;
+/// Writing podcasts to disk
+pub mod output;
+/// Searching for podcasts
+#[cfg(feature = "search")]
+pub mod search;
+
+pub use error::Error;
+pub use feed::{Podcast, Episode, EpisodeType, Transcript, Extractor, ExtractorRegistry, download_feed, download_feed_with};
+pub use output::{
+    WriteOptions, DownloadArchive, EpisodeFilter, EpisodeInfo,
+    EpisodeRanges, select_episodes,
+    Sanitize,
+    Overwrite, Collision, Action, SkipReason,
+    PlannedEpisode, plan_podcast,
+    PlaylistFormat, MediaServer,
+    LocalFeedOptions, generate_feed,
+    Progress, DownloadProgress,
+    download_podcast, download_image, write_description,
+};
+#[cfg(feature = "search")]
+pub use search::{search, SearchResult};
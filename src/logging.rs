@@ -1,9 +1,14 @@
 use log::{Level, LevelFilter, Metadata};
 use colored::{Color, Colorize};
-use crate::{Podcast, search::SearchResult, Error};
+use indicatif::MultiProgress;
+use podcast_dl::{Action, Podcast, PlannedEpisode, ExtractorRegistry};
+#[cfg(feature = "search")]
+use podcast_dl::SearchResult;
 
 /// Setup logging system
-pub fn setup_logger(level: LevelFilter) -> Result<(), fern::InitError> {
+///
+/// Messages are printed through `progress` so they do not break up progress bars.
+pub fn setup_logger(level: LevelFilter, progress: MultiProgress) -> Result<(), fern::InitError> {
     fern::Dispatch::new()
         .format(|out, message, record| {
             let (first, rest, color) = format_log_message(
@@ -22,7 +27,9 @@ pub fn setup_logger(level: LevelFilter) -> Result<(), fern::InitError> {
             (metadata.level() != Level::Debug && metadata.level() != Level::Trace)
             || filter_log_message(metadata)
         })
-        .chain(std::io::stderr())
+        .chain(fern::Output::call(move |record| {
+            progress.suspend(|| eprintln!("{}", record.args()))
+        }))
         .apply()?;
     Ok(())
 }
@@ -48,7 +55,7 @@ fn format_log_message(msg: String, level: Level, target: &str) -> (String, Strin
             let first_word = msg[..split].to_string();
             let rest = msg[split+1..].to_string();
             let color = match first_word.as_str() {
-                "Downloading" | "Skipping" => Color::Blue,
+                "Downloading" | "Skipping" | "Progress" | "Saving" | "Overwriting" | "Wrote" => Color::Blue,
                 _ => Color::BrightYellow,
             };
             (first_word, rest, color)
@@ -64,11 +71,11 @@ fn text_wrap(text: &str, length: u32) -> String {
             output.push('\n');
             current_line_length = 0;
         }
-        output.push_str(&part);
+        output.push_str(part);
         output.push(' ');
         current_line_length += part.len() as u32;
     }
-    return output;
+    output
 }
 
 fn print_title(title: &str, value: &str) {
@@ -78,7 +85,7 @@ fn print_title(title: &str, value: &str) {
 pub fn print_podcast(podcast: &Podcast) {
     print_title("Title", &podcast.title);
     if let Some(description) = &podcast.description {
-        print_title("Description", &text_wrap(&description, 40));
+        print_title("Description", &text_wrap(description, 40));
     }
     println!("{}", "Episodes".cyan().bold());
     for episode in &podcast.episodes {
@@ -86,6 +93,89 @@ pub fn print_podcast(podcast: &Podcast) {
     }
 }
 
+/// Widest episode title shown in dry run tables
+const PLAN_TITLE_WIDTH: usize = 40;
+
+/// Prints a table of what a download would do
+pub fn print_plan(planned: &[PlannedEpisode], with_sizes: bool) {
+    let title_width = planned.iter()
+        .map(|planned| planned.episode.title.chars().count())
+        .max()
+        .unwrap_or(0)
+        .clamp("Episode".len(), PLAN_TITLE_WIDTH);
+    let header = match with_sizes {
+        true => format!("{:<16} {:<title_width$} {:>10} Path", "Action", "Episode", "Size"),
+        false => format!("{:<16} {:<title_width$} Path", "Action", "Episode"),
+    };
+    println!("{}", header.cyan().bold());
+    let mut total_size = 0;
+    let mut unknown_sizes = 0;
+    for planned in planned {
+        let action = planned.action.to_string();
+        let action = match &planned.action {
+            Action::Download => action.green(),
+            Action::Overwrite | Action::Compare => action.yellow(),
+            Action::Skip(_) => action.blue(),
+            Action::Fail(_) => action.red(),
+        };
+        let title = truncate_chars(&planned.episode.title, title_width);
+        let path = match (&planned.path, &planned.action) {
+            (_, Action::Fail(error)) => error.clone(),
+            (Some(path), _) => path.display().to_string(),
+            (None, _) => String::new(),
+        };
+        let downloads = matches!(planned.action, Action::Download | Action::Overwrite | Action::Compare);
+        if with_sizes {
+            let size = match planned.size {
+                Some(size) => indicatif::HumanBytes(size).to_string(),
+                None if downloads => "?".to_string(),
+                None => String::new(),
+            };
+            println!("{:<16} {:<title_width$} {:>10} {}", action, title, size, path);
+        } else {
+            println!("{:<16} {:<title_width$} {}", action, title, path);
+        }
+        match (planned.size, downloads) {
+            (Some(size), true) => total_size += size,
+            (None, true) => unknown_sizes += 1,
+            _ => (),
+        }
+    }
+    let count = |f: fn(&Action) -> bool| planned.iter().filter(|planned| f(&planned.action)).count();
+    println!(
+        "{} to download, {} to overwrite, {} skipped, {} failed",
+        count(|action| *action == Action::Download),
+        count(|action| matches!(action, Action::Overwrite | Action::Compare)),
+        count(|action| matches!(action, Action::Skip(_))),
+        count(|action| matches!(action, Action::Fail(_))),
+    );
+    if with_sizes {
+        match unknown_sizes {
+            0 => println!("Total size: {}", indicatif::HumanBytes(total_size)),
+            n => println!("Total size: {} ({} episodes of unknown size)", indicatif::HumanBytes(total_size), n),
+        }
+    }
+}
+
+/// Shortens `input` to at most `len` characters
+fn truncate_chars(input: &str, len: usize) -> String {
+    if input.chars().count() > len {
+        input.chars().take(len.saturating_sub(1)).collect::<String>() + "…"
+    } else {
+        input.to_string()
+    }
+}
+
+pub fn print_extractors(extractors: &ExtractorRegistry) {
+    for extractor in extractors.extractors() {
+        println!("{}", extractor.name().cyan().bold());
+        for pattern in extractor.patterns() {
+            println!("  {}", pattern);
+        }
+    }
+}
+
+#[cfg(feature = "search")]
 pub fn shorten_to_length(input: &str, len: usize) -> String {
     if input.len() > len {
        input[0..len].to_string() + "…"
@@ -113,12 +203,3 @@ pub fn print_search_results(search_results: &Vec<SearchResult>) {
         );
     }
 }
-
-pub fn print_download_status(result: &Result<(), Error>) {
-    if let Err(error) = result {
-        match error {
-            Error::FileExists(title) => log::info!("Skipping {}", title),
-            e => log::error!("{}", e),
-        }
-    }
-}
@@ -1,25 +1,22 @@
 mod args;
-mod error;
-mod feed;
 mod logging;
-#[cfg(feature = "search")]
-mod search;
-mod output;
-
-pub use error::Error;
-pub use feed::{Podcast, Episode};
+mod progress;
 
+use podcast_dl::{Error, EpisodeFilter, ExtractorRegistry, WriteOptions};
+use indicatif::MultiProgress;
 use structopt::StructOpt;
-use output::WriteOptions;
 use args::Command;
 
 #[tokio::main]
-async fn main() -> Result<(), error::Error> {
+async fn main() -> Result<(), Error> {
     let args = args::Args::from_args();
-    logging::setup_logger(args.log_level)?;
+    let multi = MultiProgress::new();
+    logging::setup_logger(args.log_level, multi.clone())?;
     match args.command {
-        Command::Download(download_args) => download(&download_args).await?,
+        Command::Download(download_args) => download(&download_args, &multi).await?,
         Command::Print(print_args) => print(&print_args).await?,
+        Command::ListExtractors => logging::print_extractors(&ExtractorRegistry::default()),
+        Command::GenerateFeed(feed_args) => generate_feed(&feed_args)?,
         #[cfg(feature = "search")]
         Command::Search(search_args) => search(&search_args).await?,
     }
@@ -27,36 +24,82 @@ async fn main() -> Result<(), error::Error> {
 }
 
 /// Download episodes
-async fn download(args: &args::Download) -> Result<(), error::Error> {
-    let podcast = feed::download_feed(&args.url).await?;
+async fn download(args: &args::Download, multi: &MultiProgress) -> Result<(), Error> {
     let write_options = WriteOptions {
         limit: args.limit,
         offset: args.offset,
+        episodes: args.episodes.clone(),
         oldest: args.oldest,
+        filter: EpisodeFilter {
+            after: args.after,
+            before: args.before,
+            match_title: args.match_title.clone(),
+            reject_title: args.reject_title.clone(),
+            min_duration: args.min_duration,
+            max_duration: args.max_duration,
+            episode_type: args.episode_type,
+            explicit: (args.explicit || args.clean).then_some(args.explicit),
+        },
         template: args.output.clone(),
+        output_dir: args.output_dir.clone(),
+        template_fallback: Some(args.output_fallback.clone()),
+        sanitize: args.sanitize,
+        overwrite: args.overwrite,
+        collision: args.collision,
         write_episode_description: args.write_episode_description,
+        write_info_json: args.write_info_json,
+        write_playlist: args.write_playlist.then_some(args.playlist_format),
+        media_server: args.media_server,
         remove_from_output: args.remove_from_output.clone(),
+        jobs: args.jobs,
+        download_archive: args.download_archive.clone(),
+        embed_metadata: args.embed_metadata,
+        no_verify: args.no_verify,
+        progress: Some(progress::create_progress(multi)),
     };
-    output::download_podcast(&podcast, &write_options).await?;
-    if args.download_image {
-        output::download_image(&podcast, &write_options).await?;
+    write_options.validate()?;
+    let podcast = podcast_dl::download_feed(&args.url).await?;
+    if args.dry_run {
+        let planned = podcast_dl::plan_podcast(&podcast, &write_options, args.total_size).await?;
+        logging::print_plan(&planned, args.total_size);
+        return Ok(());
     }
-    if args.write_description {
-        output::write_description(&podcast, &write_options).await?;
+    podcast_dl::download_podcast(&podcast, &write_options).await?;
+    // Media servers get their own cover image and metadata files
+    if args.download_image && args.media_server.is_none() {
+        podcast_dl::download_image(&podcast, &write_options).await?;
+    }
+    if args.write_description && args.media_server.is_none() {
+        podcast_dl::write_description(&podcast, &write_options).await?;
     }
     Ok(())
 }
 
 /// Print podcast info
-async fn print(args: &args::Print) -> Result<(), error::Error> {
-    let podcast = feed::download_feed(&args.url).await?;
+async fn print(args: &args::Print) -> Result<(), Error> {
+    let podcast = podcast_dl::download_feed(&args.url).await?;
     logging::print_podcast(&podcast);
     Ok(())
 }
 
+/// Generate a feed for downloaded episodes
+fn generate_feed(args: &args::GenerateFeed) -> Result<(), Error> {
+    let options = podcast_dl::LocalFeedOptions {
+        directory: args.directory.clone(),
+        base_url: args.base_url.clone(),
+        download_archive: args.download_archive.clone(),
+    };
+    let feed = podcast_dl::generate_feed(&options)?;
+    match &args.output {
+        Some(path) => std::fs::write(path, feed)?,
+        None => println!("{}", feed),
+    }
+    Ok(())
+}
+
 #[cfg(feature = "search")]
-async fn search(args: &args::Search) -> Result<(), error::Error> {
-    let search_results = search::search(&args.search_terms).await?;
+async fn search(args: &args::Search) -> Result<(), Error> {
+    let search_results = podcast_dl::search(&args.search_terms).await?;
     logging::print_search_results(&search_results);
     Ok(())
 }
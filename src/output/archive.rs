@@ -0,0 +1,64 @@
+use crate::{Podcast, Episode, error::Error};
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// File recording downloaded episodes, one `<feed url> <episode guid>` per line
+///
+/// Episodes without a guid are recorded by their audio link instead.
+pub struct DownloadArchive {
+    path: PathBuf,
+    entries: Mutex<HashSet<String>>,
+}
+
+impl DownloadArchive {
+    /// Reads archive at `path`. A missing file is treated as an empty archive.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        let entries = match std::fs::read_to_string(path) {
+            Ok(content) => content.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Returns true if `episode` has been recorded
+    pub fn contains(&self, podcast: &Podcast, episode: &Episode) -> bool {
+        self.entries.lock().unwrap()
+            .contains(&archive_key(podcast, episode))
+    }
+
+    /// Records `episode` as downloaded
+    pub fn record(&self, podcast: &Podcast, episode: &Episode) -> Result<(), Error> {
+        let key = archive_key(podcast, episode);
+        let mut entries = self.entries.lock().unwrap();
+        if entries.contains(&key) {
+            return Ok(());
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", key)
+            .or(Err(Error::WriteToFile("download archive")))?;
+        entries.insert(key);
+        Ok(())
+    }
+}
+
+/// Line identifying `episode` in the archive
+fn archive_key(podcast: &Podcast, episode: &Episode) -> String {
+    let feed = podcast.feed_url.as_deref().unwrap_or(&podcast.title);
+    let id = episode.guid.as_deref().unwrap_or(&episode.link);
+    format!("{} {}", feed, id)
+}
@@ -0,0 +1,253 @@
+use crate::error::Error;
+use super::{EpisodeLog, Progress};
+use std::path::{Path, PathBuf};
+use reqwest::{Client, StatusCode, header};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// A file to download
+pub struct Download<'a> {
+    pub url: &'a str,
+    pub path: &'a Path,
+    /// Describes the file in error messages
+    pub kind: &'static str,
+    /// Shown in progress updates
+    pub name: &'a str,
+    pub progress: Option<&'a dyn Progress>,
+    /// Receives messages about the download
+    pub log: &'a EpisodeLog,
+    /// Checks the finished file is audio or video of the expected size
+    pub verify: Option<Verify<'a>>,
+}
+
+/// Expectations for a downloaded media file
+pub struct Verify<'a> {
+    /// Length from the rss enclosure
+    pub enclosure_length: Option<u64>,
+    /// Mime type from the rss enclosure
+    pub enclosure_type: Option<&'a str>,
+}
+
+/// Downloads a file one chunk at a time
+///
+/// Data is written to `<path>.part` and moved to `path` once the download is complete and
+/// verified. An existing part file is resumed with a range request if the server supports it and
+/// restarted otherwise. If the server rejects the range, the part file is kept when it is already
+/// complete. A part file failing verification is removed.
+pub async fn download_file(client: &Client, download: &Download<'_>) -> Result<(), Error> {
+    let part_path = part_path(download.path);
+    let existing = tokio::fs::metadata(&part_path).await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    let mut request = client.get(download.url);
+    if existing > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", existing));
+    }
+    let response = request.send().await?;
+    let mut action = response_action(response.status(), content_range(&response), existing);
+    if matches!(action, ResponseAction::Complete | ResponseAction::VerifyPart) {
+        // Complete part files have the size the server reported
+        let total = (action == ResponseAction::Complete).then_some(existing);
+        let complete = match &download.verify {
+            Some(verify) => verify_file(&part_path, verify, total, None, download.log).await.is_ok(),
+            None => action == ResponseAction::Complete,
+        };
+        action = if complete { ResponseAction::Complete } else { ResponseAction::Restart };
+    }
+    let response = match action {
+        ResponseAction::Complete => {
+            download.log.debug(format!("Download of {} is already complete", part_path.display()));
+            tokio::fs::rename(&part_path, download.path).await?;
+            return Ok(());
+        },
+        ResponseAction::Restart => {
+            download.log.debug(format!("Server did not resume {} at {} bytes, restarting", part_path.display(), existing));
+            let response = client.get(download.url).send().await?;
+            action = response_action(response.status(), content_range(&response), 0);
+            response
+        },
+        _ => response,
+    };
+    let mut response = response.error_for_status()?;
+    let resumed = match action {
+        ResponseAction::Append => true,
+        ResponseAction::Replace => false,
+        _ => return Err(Error::Verification(
+            download.name.to_string(),
+            "server sent part of the file without a range request".to_string(),
+        )),
+    };
+    if resumed {
+        download.log.debug(format!("Resuming download of {} at {} bytes", part_path.display(), existing));
+    }
+    let downloaded = if resumed { existing } else { 0 };
+    let total = response.content_length().map(|length| length + downloaded);
+    let content_type = response.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let mut download_progress = download.progress
+        .map(|progress| progress.start_download(download.name, downloaded, total));
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part_path).await?;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await
+            .or(Err(Error::WriteToFile(download.kind)))?;
+        if let Some(download_progress) = &mut download_progress {
+            download_progress.advance(chunk.len() as u64);
+        }
+    }
+    file.flush().await
+        .or(Err(Error::WriteToFile(download.kind)))?;
+    drop(file);
+    if let Some(verify) = &download.verify {
+        if let Err(reason) = verify_file(&part_path, verify, total, content_type.as_deref(), download.log).await {
+            tokio::fs::remove_file(&part_path).await?;
+            return Err(Error::Verification(download.name.to_string(), reason));
+        }
+    }
+    tokio::fs::rename(&part_path, download.path).await?;
+    Ok(())
+}
+
+/// Path of the file used while downloading to `path`
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name()
+        .unwrap_or_default()
+        .to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// What to do with the response to a download request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseAction {
+    /// Append the body to the part file
+    Append,
+    /// Replace the part file with the body
+    Replace,
+    /// Keep the part file, which already has the size of the whole file, if it passes verification
+    Complete,
+    /// Keep the part file if it passes verification. The server rejected the range without
+    /// telling the size of the file.
+    VerifyPart,
+    /// Request the whole file again without a range
+    Restart,
+    /// Give up, the server sent part of the file without being asked for it
+    Fail,
+}
+
+/// Decides what to do with a response to a request for the file with `existing` bytes in its
+/// part file. A range request is only sent if `existing` is not 0.
+///
+/// Error statuses other than 416 are `Replace` and fail when checked.
+pub fn response_action(status: StatusCode, content_range: Option<&str>, existing: u64) -> ResponseAction {
+    match status {
+        // The part file is either complete or larger than the file on the server
+        StatusCode::RANGE_NOT_SATISFIABLE if existing > 0 => {
+            // Like `bytes */1234`
+            let total = content_range
+                .and_then(|value| value.rsplit('/').next())
+                .and_then(|value| value.parse::<u64>().ok());
+            match total {
+                Some(total) if total == existing => ResponseAction::Complete,
+                Some(_) => ResponseAction::Restart,
+                None => ResponseAction::VerifyPart,
+            }
+        },
+        StatusCode::PARTIAL_CONTENT => {
+            // Like `bytes 1000-1999/2000`
+            let start = content_range
+                .and_then(|value| value.strip_prefix("bytes "))
+                .and_then(|value| value.split('-').next())
+                .and_then(|value| value.parse::<u64>().ok());
+            match start {
+                Some(start) if start == existing && existing > 0 => ResponseAction::Append,
+                // Partial responses starting elsewhere can neither be appended nor saved as the whole file
+                _ if existing > 0 => ResponseAction::Restart,
+                Some(0) => ResponseAction::Replace,
+                _ => ResponseAction::Fail,
+            }
+        },
+        _ => ResponseAction::Replace,
+    }
+}
+
+/// `Content-Range` header of `response`
+fn content_range(response: &reqwest::Response) -> Option<&str> {
+    response.headers()
+        .get(header::CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+}
+
+/// Checks that the file at `path` is a complete audio or video file
+///
+/// Enclosure lengths are often off because of dynamically inserted ads, so they are only
+/// enforced when the server did not send a `Content-Length`.
+async fn verify_file(
+    path: &Path,
+    verify: &Verify<'_>,
+    content_length: Option<u64>,
+    content_type: Option<&str>,
+    log: &EpisodeLog,
+) -> Result<(), String> {
+    if let Some(content_type) = content_type {
+        if content_type.starts_with("text/") {
+            return Err(format!("server returned {}", content_type));
+        }
+    }
+    let size = tokio::fs::metadata(path).await
+        .map_err(|e| e.to_string())?
+        .len();
+    match (content_length, verify.enclosure_length) {
+        (Some(expected), _) if size != expected => {
+            return Err(format!("expected {} bytes from Content-Length, got {}", expected, size));
+        },
+        (None, Some(expected)) if expected > 0 && size != expected => {
+            return Err(format!("expected {} bytes from enclosure, got {}", expected, size));
+        },
+        (Some(_), Some(expected)) if expected > 0 && size != expected => {
+            log.debug(format!("Size of {} differs from enclosure length {}", path.display(), expected));
+        },
+        _ => (),
+    }
+    let mut header = [0; 12];
+    let mut file = tokio::fs::File::open(path).await
+        .map_err(|e| e.to_string())?;
+    let read = file.read(&mut header).await
+        .map_err(|e| e.to_string())?;
+    if !is_media(&header[..read]) {
+        return Err(match verify.enclosure_type {
+            Some(expected) => format!("expected {}, file is not audio or video", expected),
+            None => "file is not audio or video".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Recognizes audio and video files by their first bytes
+pub fn is_media(header: &[u8]) -> bool {
+    match header {
+        // Mp3 with id3 tag
+        [b'I', b'D', b'3', ..] => true,
+        // Mpeg audio or aac frame
+        [0xFF, second, ..] if second & 0xE0 == 0xE0 => true,
+        // Mp4, m4a and mov
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => true,
+        // Older QuickTime files start with other atoms
+        [_, _, _, _, b'm', b'o', b'o', b'v', ..]
+        | [_, _, _, _, b'w', b'i', b'd', b'e', ..]
+        | [_, _, _, _, b'm', b'd', b'a', b't', ..]
+        | [_, _, _, _, b'f', b'r', b'e', b'e', ..] => true,
+        // Ogg, opus and flac
+        [b'O', b'g', b'g', b'S', ..] | [b'f', b'L', b'a', b'C', ..] => true,
+        // Wav
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => true,
+        // Webm and mkv
+        [0x1A, 0x45, 0xDF, 0xA3, ..] => true,
+        _ => false,
+    }
+}
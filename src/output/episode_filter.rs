@@ -0,0 +1,76 @@
+use crate::{Episode, feed::EpisodeType};
+use chrono::NaiveDate;
+use regex::Regex;
+
+/// Conditions episodes have to meet to be downloaded
+///
+/// Episodes missing a value a condition depends on are left out.
+#[derive(Debug, Default, Clone)]
+pub struct EpisodeFilter {
+    /// Only episodes published on or after this day
+    pub after: Option<NaiveDate>,
+    /// Only episodes published on or before this day
+    pub before: Option<NaiveDate>,
+    /// Only episodes with titles matching this regex
+    pub match_title: Option<Regex>,
+    /// Leave out episodes with titles matching this regex
+    pub reject_title: Option<Regex>,
+    /// Only episodes at least this many seconds long
+    pub min_duration: Option<u64>,
+    /// Only episodes at most this many seconds long
+    pub max_duration: Option<u64>,
+    /// Only episodes of this type. Episodes without a type are full episodes.
+    pub episode_type: Option<EpisodeType>,
+    /// Only explicit (`true`) or clean (`false`) episodes. Episodes not marked explicit are clean.
+    pub explicit: Option<bool>,
+}
+
+impl EpisodeFilter {
+
+    /// Checks if `episode` meets all conditions
+    pub fn matches(&self, episode: &Episode) -> bool {
+        let date = episode.pub_date.map(|x| x.date_naive());
+        if let Some(after) = self.after {
+            if date.is_none_or(|date| date < after) {
+                return false;
+            }
+        }
+        if let Some(before) = self.before {
+            if date.is_none_or(|date| date > before) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.match_title {
+            if !pattern.is_match(&episode.title) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.reject_title {
+            if pattern.is_match(&episode.title) {
+                return false;
+            }
+        }
+        if let Some(min_duration) = self.min_duration {
+            if episode.duration.is_none_or(|duration| duration < min_duration) {
+                return false;
+            }
+        }
+        if let Some(max_duration) = self.max_duration {
+            if episode.duration.is_none_or(|duration| duration > max_duration) {
+                return false;
+            }
+        }
+        if let Some(episode_type) = self.episode_type {
+            if episode.episode_type.unwrap_or(EpisodeType::Full) != episode_type {
+                return false;
+            }
+        }
+        if let Some(explicit) = self.explicit {
+            if episode.explicit.unwrap_or(false) != explicit {
+                return false;
+            }
+        }
+        true
+    }
+
+}
@@ -0,0 +1,46 @@
+use log::Level;
+use std::sync::Mutex;
+
+/// Logs messages right away, for downloads not running next to others
+pub static UNBUFFERED: EpisodeLog = EpisodeLog::new(false);
+
+/// Log messages of a single episode
+///
+/// When downloading several episodes at the same time, messages are kept until the episode is done
+/// and then logged together, so lines of different episodes do not interleave.
+pub struct EpisodeLog {
+    buffered: bool,
+    messages: Mutex<Vec<(Level, String)>>,
+}
+
+impl EpisodeLog {
+    pub const fn new(buffered: bool) -> Self {
+        Self { buffered, messages: Mutex::new(Vec::new()) }
+    }
+
+    pub fn log(&self, level: Level, message: String) {
+        if !self.buffered {
+            log::log!(level, "{}", message);
+            return;
+        }
+        if let Ok(mut messages) = self.messages.lock() {
+            messages.push((level, message));
+        }
+    }
+
+    pub fn info(&self, message: String) {
+        self.log(Level::Info, message);
+    }
+
+    pub fn debug(&self, message: String) {
+        self.log(Level::Debug, message);
+    }
+
+    /// Logs the buffered messages
+    pub fn flush(self) {
+        let messages = self.messages.into_inner().unwrap_or_default();
+        for (level, message) in messages {
+            log::log!(level, "{}", message);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::Error;
+use regex::Regex;
+use super::template::split_unquoted;
+
+/// Transformation of a value in the output template, like `{episode_title|lower}`
+#[derive(Debug)]
+pub enum Filter {
+    /// Lowercase
+    Lower,
+    /// Uppercase
+    Upper,
+    /// Lowercase words joined by `-`
+    Slug,
+    /// Keep at most this many characters
+    Truncate(usize),
+    /// Replace every match of a regex. The replacement can refer to groups like `$1`.
+    Replace(Regex, String),
+    /// Value used when the variable is missing or empty
+    Default(String),
+    /// Pad numbers with zeros to this many digits
+    Pad(usize),
+}
+
+impl Filter {
+    /// Parses a filter like `truncate(80)` or `replace("^Ep\\. ", "")`
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let text = text.trim();
+        let (name, args) = match text.split_once('(') {
+            Some((name, args)) => {
+                let args = args.strip_suffix(')')
+                    .ok_or_else(|| invalid(text, "missing `)`"))?;
+                let args = split_unquoted(args, ',').into_iter()
+                    .map(parse_argument)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|reason| invalid(text, reason))?;
+                (name.trim(), args)
+            },
+            None => (text, Vec::new()),
+        };
+        let filter = match (name, args.as_slice()) {
+            ("lower", []) => Self::Lower,
+            ("upper", []) => Self::Upper,
+            ("slug", []) => Self::Slug,
+            ("truncate", [length]) => Self::Truncate(parse_number(text, length)?),
+            ("pad", [width]) => Self::Pad(parse_number(text, width)?),
+            ("default", [value]) => Self::Default(value.clone()),
+            ("replace", [pattern, replacement]) => Self::Replace(
+                Regex::new(pattern).map_err(|e| invalid(text, &e.to_string()))?,
+                replacement.clone(),
+            ),
+            ("lower" | "upper" | "slug" | "truncate" | "pad" | "default" | "replace", _) => {
+                return Err(invalid(text, "wrong number of arguments"));
+            },
+            _ => return Err(invalid(text, "unknown filter")),
+        };
+        Ok(filter)
+    }
+
+    /// Applies the filter to a value, which is `None` if the variable is missing
+    pub fn apply(&self, value: Option<String>) -> Option<String> {
+        if let Self::Default(default) = self {
+            return match value {
+                Some(value) if !value.is_empty() => Some(value),
+                _ => Some(default.clone()),
+            };
+        }
+        let value = value?;
+        let filtered = match self {
+            Self::Lower => value.to_lowercase(),
+            Self::Upper => value.to_uppercase(),
+            Self::Slug => slug(&value),
+            Self::Truncate(length) => match value.char_indices().nth(*length) {
+                Some((end, _)) => value[..end].trim_end().to_string(),
+                None => value,
+            },
+            Self::Replace(pattern, replacement) => pattern.replace_all(&value, replacement.as_str()).into_owned(),
+            Self::Pad(width) => pad_number(&value, *width),
+            Self::Default(_) => unreachable!(),
+        };
+        Some(filtered)
+    }
+}
+
+fn invalid(filter: &str, reason: &str) -> Error {
+    Error::InvalidTemplate(format!("filter `{}`: {}", filter, reason))
+}
+
+fn parse_number(filter: &str, argument: &str) -> Result<usize, Error> {
+    argument.parse().map_err(|_e| invalid(filter, "expected a number"))
+}
+
+/// Parses a filter argument, which is either a number or a string in double quotes
+fn parse_argument(argument: &str) -> Result<String, &'static str> {
+    let argument = argument.trim();
+    let quoted = match argument.strip_prefix('"').and_then(|x| x.strip_suffix('"')) {
+        Some(quoted) => quoted,
+        None if argument.chars().all(|c| c.is_ascii_digit()) && !argument.is_empty() => return Ok(argument.to_string()),
+        None => return Err("arguments must be numbers or strings in double quotes"),
+    };
+    // Only `\"` and `\\` are escapes, so regexes like `\d` can be written as is
+    let mut unescaped = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(&next)) if next == '"' || next == '\\' => {
+                unescaped.push(next);
+                chars.next();
+            },
+            _ => unescaped.push(c),
+        }
+    }
+    Ok(unescaped)
+}
+
+/// Lowercases `value` and joins its alphanumeric words with `-`
+fn slug(value: &str) -> String {
+    value.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Pads `value` with zeros if it is a number
+fn pad_number(value: &str, width: usize) -> String {
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+        return value.to_string();
+    }
+    format!("{:0>width$}", value, width = width)
+}
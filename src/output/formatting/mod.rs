@@ -0,0 +1,216 @@
+use crate::{Podcast, Episode, Error};
+use std::{
+    fmt::{self, Write},
+    collections::HashMap,
+    path::PathBuf,
+};
+use chrono::{DateTime, FixedOffset};
+use rt_format::{Format, FormatArgument, NoNamedArguments, ParsedFormat, Specifier};
+
+use super::WriteOptions;
+
+/// Making substituted values safe for file names
+mod sanitize;
+/// Keeping formatted paths inside the output directory
+mod path;
+/// Parsing output templates
+mod template;
+/// Filters transforming values in output templates
+mod filter;
+
+pub use sanitize::{Sanitize, sanitize_value};
+
+/// Value of missing variables without a fallback in the options
+const DEFAULT_FALLBACK: &str = "UNKNOWN";
+/// Format of dates without a format in the template
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, PartialEq, Clone)]
+enum Variant {
+    String(String),
+    OptionInt(Option<usize>),
+    OptionString(Option<String>),
+    Date(Option<DateTime<FixedOffset>>),
+}
+
+fn format_int(
+    variant: &Variant,
+    f: &mut fmt::Formatter,
+    format_function: &dyn Fn(&usize, &mut fmt::Formatter) -> fmt::Result,
+) -> fmt::Result {
+    match variant {
+        Variant::OptionInt(Some(val)) => format_function(val, f),
+        _ => Err(fmt::Error)
+    }
+}
+
+impl FormatArgument for Variant {
+    fn supports_format(&self, spec: &Specifier) -> bool {
+        match self {
+            Self::String(_) | Self::OptionString(_) | Self::Date(_) | Self::OptionInt(None) => {
+                matches!(spec.format, Format::Display | Format::Debug)
+            },
+            Self::OptionInt(Some(_)) => true,
+        }
+    }
+
+    /// Missing values are handled before formatting, so they are an error here
+    fn fmt_display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::String(val) => fmt::Display::fmt(&val, f),
+            Self::OptionString(x) => match x {
+                Some(value) => fmt::Display::fmt(&value, f),
+                None => Err(fmt::Error),
+            },
+            Self::OptionInt(x) => match x {
+                Some(value) => fmt::Display::fmt(&value, f),
+                None => Err(fmt::Error),
+            },
+            Self::Date(x) => match x {
+                Some(date) => fmt::Display::fmt(&date.format(DEFAULT_DATE_FORMAT), f),
+                None => Err(fmt::Error),
+            },
+        }
+    }
+
+    fn fmt_debug(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+
+    fn fmt_octal(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_int(self, f, &fmt::Octal::fmt)
+    }
+
+    fn fmt_lower_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_int(self, f, &fmt::LowerHex::fmt)
+    }
+
+    fn fmt_upper_hex(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_int(self, f, &fmt::UpperHex::fmt)
+    }
+
+    fn fmt_binary(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_int(self, f, &fmt::Binary::fmt)
+    }
+
+    fn fmt_lower_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_int(self, f, &fmt::LowerExp::fmt)
+    }
+
+    fn fmt_upper_exp(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format_int(self, f, &fmt::UpperExp::fmt)
+    }
+
+    fn to_usize(&self) -> Result<usize, ()> {
+        match self {
+            Variant::OptionInt(Some(val)) => Ok(*val),
+            _ => Err(()),
+        }
+    }
+}
+
+type OutputOptions = HashMap<&'static str, Variant>;
+/// Values available in the output template
+fn episode_options(podcast: &Podcast, episode: &Episode) -> OutputOptions {
+    HashMap::from([
+        ("podcast_title", Variant::String(podcast.title.clone())),
+        ("podcast_author", Variant::OptionString(podcast.author.clone())),
+        ("episode_title", Variant::String(episode.title.clone())),
+        ("episode_index", Variant::OptionInt(episode.index)),
+        ("episode_author", Variant::OptionString(episode.author.clone())),
+        ("episode_date", Variant::Date(episode.pub_date)),
+        ("episode_number", Variant::OptionInt(episode.episode_number)),
+        ("episode_duration", Variant::OptionInt(episode.duration.map(|x| x as usize))),
+        ("episode_guid", Variant::OptionString(episode.guid.clone())),
+        ("season", Variant::OptionInt(episode.season)),
+        ("ext", Variant::OptionString(episode.extension())),
+    ])
+}
+
+pub fn format_episode(
+    podcast: &Podcast,
+    episode: &Episode,
+    options: &WriteOptions
+) -> Result<String, Error> {
+    let named_options = episode_options(podcast, episode);
+    let segments = template::parse(&options.template)?;
+    let mut formatted = render(&segments, &named_options, options, false)?
+        .unwrap_or_default();
+    for remove_string in &options.remove_from_output {
+        formatted = formatted.replace(remove_string, "");
+    }
+    Ok(sanitize::truncate_components(&formatted, sanitize::MAX_COMPONENT_BYTES))
+}
+
+/// Renders template segments, or returns `None` if a variable is missing in an `optional` section
+fn render(
+    segments: &[template::Segment],
+    named_options: &OutputOptions,
+    options: &WriteOptions,
+    optional: bool,
+) -> Result<Option<String>, Error> {
+    let mut rendered = String::new();
+    for segment in segments {
+        match segment {
+            template::Segment::Literal(text) => rendered.push_str(text),
+            template::Segment::Field { name, spec, filters } => {
+                let value = named_options.get(name)
+                    .ok_or_else(|| Error::InvalidTemplate(format!("unknown variable `{}`", name)))?;
+                let value = filters.iter()
+                    .fold(format_variant(value, spec)?, |value, filter| filter.apply(value));
+                let value = match value {
+                    Some(value) => value,
+                    None if optional => return Ok(None),
+                    None => options.template_fallback.clone()
+                        .unwrap_or_else(|| DEFAULT_FALLBACK.to_string()),
+                };
+                rendered.push_str(&sanitize::sanitize_value(&value, options.sanitize));
+            },
+            template::Segment::Optional(inner) => {
+                if let Some(text) = render(inner, named_options, options, true)? {
+                    rendered.push_str(&text);
+                }
+            },
+        }
+    }
+    Ok(Some(rendered))
+}
+
+/// Formats a single value with the format after `:` in the template, or `None` if it is missing
+///
+/// Dates use strftime-style formats, everything else uses the format of `format!`. Strings holding
+/// numbers are formatted as numbers, so `{x:03}` pads them with zeros.
+fn format_variant(variant: &Variant, spec: &str) -> Result<Option<String>, Error> {
+    let mut formatted = String::new();
+    let number;
+    let variant = match variant {
+        Variant::Date(Some(date)) => {
+            let date_format = if spec.is_empty() { DEFAULT_DATE_FORMAT } else { spec };
+            write!(formatted, "{}", date.format(date_format))
+                .map_err(|_e| Error::InvalidTemplate(format!("date format `{}`", spec)))?;
+            return Ok(Some(formatted));
+        },
+        Variant::Date(None) | Variant::OptionInt(None) | Variant::OptionString(None) => return Ok(None),
+        Variant::String(x) | Variant::OptionString(Some(x)) if !spec.is_empty() => {
+            match x.trim().parse() {
+                Ok(value) => {
+                    number = Variant::OptionInt(Some(value));
+                    &number
+                },
+                Err(_) => variant,
+            }
+        },
+        _ => variant,
+    };
+    let format = format!("{{:{}}}", spec);
+    let args = ParsedFormat::parse(&format, std::slice::from_ref(variant), &NoNamedArguments)
+        .map_err(|_e| Error::StringFormat)?;
+    write!(formatted, "{}", args).map_err(|_e| Error::StringFormat)?;
+    Ok(Some(formatted))
+}
+
+/// Formats the path of `episode` and checks that it stays inside the output directory
+pub fn episode_path(podcast: &Podcast, episode: &Episode, options: &WriteOptions) -> Result<PathBuf, Error> {
+    let formatted = format_episode(podcast, episode, options)?;
+    path::resolve(&formatted, &options.template, options.output_dir.as_deref())
+}
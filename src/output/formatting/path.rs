@@ -0,0 +1,48 @@
+use crate::Error;
+use std::path::{Component, Path, PathBuf};
+
+/// Joins a formatted path to the output directory, rejecting paths outside of it
+///
+/// Without an output directory, the literal part of the template before the first variable or
+/// optional section is used as the root, so templates like
+/// `/mnt/podcasts/{podcast_title}/{episode_title}.mp3` keep working.
+pub fn resolve(formatted: &str, template: &str, output_dir: Option<&Path>) -> Result<PathBuf, Error> {
+    // Like `out/` from `out/{episode_title}` with an empty title
+    if formatted.is_empty() || formatted.ends_with(std::path::is_separator) {
+        return Err(Error::EmptyFileName(formatted.to_string()));
+    }
+    let (path, root) = match output_dir {
+        Some(dir) => (dir.join(formatted), dir.to_path_buf()),
+        None => (PathBuf::from(formatted), template_root(template)),
+    };
+    let resolved = normalize(&path)?;
+    let root = normalize(&root)?;
+    if resolved == root || !resolved.starts_with(&root) || formatted.contains('\0') {
+        return Err(Error::PathTraversal(formatted.to_string()));
+    }
+    Ok(path)
+}
+
+/// Directory of the literal template text before the first variable or optional section
+fn template_root(template: &str) -> PathBuf {
+    let literal = template.split(['{', '[']).next().unwrap_or_default();
+    match literal.rfind(std::path::is_separator) {
+        Some(i) => PathBuf::from(&literal[..=i]),
+        None => PathBuf::new(),
+    }
+}
+
+/// Makes `path` absolute and removes `.` and `..` components without touching the filesystem
+fn normalize(path: &Path) -> Result<PathBuf, Error> {
+    let mut normalized = std::env::current_dir()?;
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                normalized.pop();
+            },
+            component => normalized.push(component),
+        }
+    }
+    Ok(normalized)
+}
@@ -0,0 +1,128 @@
+use std::{fmt, str::FromStr};
+
+/// Longest file or directory name most filesystems accept, in bytes
+pub const MAX_COMPONENT_BYTES: usize = 255;
+/// Longest extension kept when truncating a file name, in bytes
+const MAX_EXTENSION_BYTES: usize = 16;
+/// Character used in place of characters not allowed in file names
+const REPLACEMENT: char = '_';
+/// Characters not allowed in file names on Windows and SMB shares
+const WINDOWS_RESERVED_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+/// File names not allowed on Windows, with or without an extension
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// How strictly values substituted into the output template are sanitized
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Sanitize {
+    /// Replace `/` and drop control characters
+    #[default]
+    Posix,
+    /// Also replace characters and names Windows does not allow
+    Windows,
+    /// Windows rules, and replace every non-ASCII character
+    Ascii,
+}
+
+impl Sanitize {
+    /// Names accepted by `from_str`
+    pub const VARIANTS: &'static [&'static str] = &["posix", "windows", "ascii"];
+}
+
+impl FromStr for Sanitize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "posix" => Ok(Self::Posix),
+            "windows" => Ok(Self::Windows),
+            "ascii" => Ok(Self::Ascii),
+            _ => Err(format!("Unknown sanitization mode: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Sanitize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Posix => "posix",
+            Self::Windows => "windows",
+            Self::Ascii => "ascii",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Makes a value substituted into the template safe to use in a file name
+///
+/// Path separators and NUL bytes are never kept, so values cannot point outside their directory.
+pub fn sanitize_value(value: &str, mode: Sanitize) -> String {
+    let mut sanitized: String = value.chars()
+        .filter_map(|c| sanitize_char(c, mode))
+        .collect();
+    // A value of `.` or `..` would refer to the current or parent directory
+    if !sanitized.is_empty() && sanitized.chars().all(|c| c == '.') {
+        sanitized = sanitized.replace('.', &REPLACEMENT.to_string());
+    }
+    if mode != Sanitize::Posix {
+        // Windows silently drops trailing dots and spaces
+        let trimmed_length = sanitized.trim_end_matches(['.', ' ']).len();
+        sanitized.truncate(trimmed_length);
+        if is_reserved_name(&sanitized) {
+            sanitized.insert(0, REPLACEMENT);
+        }
+    }
+    sanitized
+}
+
+/// Returns the replacement for a single character, or `None` if it should be dropped
+fn sanitize_char(c: char, mode: Sanitize) -> Option<char> {
+    if matches!(c, '\n' | '\r' | '\t') {
+        return Some(' ');
+    }
+    if c.is_control() {
+        return None;
+    }
+    let replace = match mode {
+        Sanitize::Posix => c == '/',
+        Sanitize::Windows => WINDOWS_RESERVED_CHARS.contains(&c),
+        Sanitize::Ascii => WINDOWS_RESERVED_CHARS.contains(&c) || !c.is_ascii(),
+    };
+    Some(if replace { REPLACEMENT } else { c })
+}
+
+/// Checks if `name` is reserved on Windows, like `CON` or `com1.txt`
+fn is_reserved_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    WINDOWS_RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// Truncates every component of `path` to at most `max_bytes` bytes
+pub fn truncate_components(path: &str, max_bytes: usize) -> String {
+    let mut truncated = String::with_capacity(path.len());
+    for part in path.split_inclusive(std::path::is_separator) {
+        let component = part.trim_end_matches(std::path::is_separator);
+        truncated.push_str(&truncate_component(component, max_bytes));
+        truncated.push_str(&part[component.len()..]);
+    }
+    truncated
+}
+
+/// Truncates a file name to at most `max_bytes` bytes on a character boundary, keeping its extension
+fn truncate_component(component: &str, max_bytes: usize) -> String {
+    if component.len() <= max_bytes {
+        return component.to_string();
+    }
+    let (stem, extension) = match component.rfind('.') {
+        Some(i) if i > 0 && component.len() - i <= MAX_EXTENSION_BYTES.min(max_bytes) => component.split_at(i),
+        _ => (component, ""),
+    };
+    let mut end = max_bytes - extension.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", stem[..end].trim_end(), extension)
+}
@@ -0,0 +1,126 @@
+use crate::Error;
+use super::filter::Filter;
+
+/// Part of an output template
+#[derive(Debug)]
+pub enum Segment<'a> {
+    /// Text copied to the output as is
+    Literal(String),
+    /// Variable replaced with its value, like `{episode_date:%Y-%m-%d|lower}`
+    Field {
+        name: &'a str,
+        /// Everything between the first `:` and the first filter
+        spec: &'a str,
+        /// Filters applied to the formatted value in order
+        filters: Vec<Filter>,
+    },
+    /// Section in `[]` left out when one of its variables is missing, like `[S{season:02} ]`
+    Optional(Vec<Segment<'a>>),
+}
+
+/// Splits `template` into literal text, variables and optional sections
+///
+/// `{{`, `}}`, `[[` and `]]` are literal braces and brackets. Brackets without a variable between
+/// them and unmatched brackets are kept as text, so templates like `[Archive] {episode_title}` stay
+/// the same.
+pub fn parse(template: &str) -> Result<Vec<Segment<'_>>, Error> {
+    let mut rest = template;
+    let (segments, _) = parse_segments(&mut rest, false)?;
+    Ok(segments)
+}
+
+/// Parses segments until the end of `rest`, or the closing `]` if `nested`
+///
+/// Also returns whether the closing `]` was found.
+fn parse_segments<'a>(rest: &mut &'a str, nested: bool) -> Result<(Vec<Segment<'a>>, bool), Error> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    while let Some(i) = rest.find(['{', '}', '[', ']']) {
+        literal.push_str(&rest[..i]);
+        let special = &rest[i..i+1];
+        *rest = &rest[i+1..];
+        // Escaped brace or bracket
+        if rest.starts_with(special) {
+            literal.push_str(special);
+            *rest = &rest[1..];
+            continue;
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(std::mem::take(&mut literal)));
+        }
+        match special {
+            "{" => {
+                let end = find_unquoted(rest, '}')
+                    .ok_or_else(|| Error::InvalidTemplate("unmatched `{`".to_string()))?;
+                segments.push(parse_field(&rest[..end])?);
+                *rest = &rest[end+1..];
+            },
+            "[" => match parse_segments(rest, true)? {
+                (inner, true) if inner.iter().any(|segment| !matches!(segment, Segment::Literal(_))) => {
+                    segments.push(Segment::Optional(inner));
+                },
+                (inner, true) => {
+                    let text = inner.into_iter()
+                        .map(|segment| match segment {
+                            Segment::Literal(text) => text,
+                            _ => String::new(),
+                        })
+                        .collect::<String>();
+                    segments.push(Segment::Literal(format!("[{}]", text)));
+                },
+                (inner, false) => {
+                    segments.push(Segment::Literal("[".to_string()));
+                    segments.extend(inner);
+                },
+            },
+            "]" if nested => return Ok((segments, true)),
+            "]" => segments.push(Segment::Literal("]".to_string())),
+            _ => return Err(Error::InvalidTemplate(format!("unmatched `{}`", special))),
+        }
+    }
+    literal.push_str(rest);
+    *rest = "";
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok((segments, false))
+}
+
+/// Parses the text between braces, like `episode_title|truncate(80)`
+fn parse_field(field: &str) -> Result<Segment<'_>, Error> {
+    let mut parts = split_unquoted(field, '|').into_iter();
+    let variable = parts.next().unwrap_or_default();
+    let (name, spec) = variable.split_once(':').unwrap_or((variable, ""));
+    let filters = parts
+        .map(Filter::parse)
+        .collect::<Result<_, _>>()?;
+    Ok(Segment::Field { name: name.trim(), spec, filters })
+}
+
+/// Finds the first `pattern` outside of double quotes
+pub fn find_unquoted(text: &str, pattern: char) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == pattern && !quoted => return Some(i),
+            _ => {},
+        }
+    }
+    None
+}
+
+/// Splits `text` at every `separator` outside of double quotes
+pub fn split_unquoted(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(i) = find_unquoted(rest, separator) {
+        parts.push(&rest[..i]);
+        rest = &rest[i+separator.len_utf8()..];
+    }
+    parts.push(rest);
+    parts
+}
@@ -0,0 +1,44 @@
+use crate::{Podcast, Episode, Error};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Contents of `<name>.info.json` files written next to episodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeInfo {
+    #[serde(flatten)]
+    pub episode: Episode,
+    /// Podcast the episode belongs to, without its episodes
+    pub podcast: Podcast,
+}
+
+impl EpisodeInfo {
+
+    /// Collects the metadata of `episode` in `podcast`
+    pub fn new(podcast: &Podcast, episode: &Episode) -> Self {
+        Self {
+            episode: episode.clone(),
+            podcast: podcast.without_episodes(),
+        }
+    }
+
+    /// Reads an info file
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let content = std::fs::read(path)?;
+        let info = serde_json::from_slice(&content)
+            .map_err(crate::error::ParseError::from)?;
+        Ok(info)
+    }
+
+    /// Writes the info file
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)
+            .or(Err(Error::WriteToFile("info json")))
+    }
+
+}
+
+/// Path of the info file for the episode at `audio_path`
+pub fn info_path(audio_path: &Path) -> PathBuf {
+    audio_path.with_extension("info.json")
+}
@@ -0,0 +1,249 @@
+use crate::{Podcast, Episode, Error};
+use super::{DownloadArchive, EpisodeInfo, info_path};
+use std::path::{Path, PathBuf};
+use rss::extension::itunes::{ITunesCategory, ITunesChannelExtension, ITunesItemExtension};
+use url::Url;
+
+/// Extensions of files included in generated feeds and their mime types
+const MEDIA_TYPES: &[(&str, &str)] = &[
+    ("mp3", "audio/mpeg"),
+    ("m4a", "audio/mp4"),
+    ("aac", "audio/aac"),
+    ("ogg", "audio/ogg"),
+    ("opus", "audio/opus"),
+    ("flac", "audio/flac"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("mov", "video/quicktime"),
+    ("webm", "video/webm"),
+    ("mkv", "video/x-matroska"),
+];
+/// Names of podcast cover images, in order of preference. Includes those written for media servers.
+const COVER_NAMES: &[&str] = &["cover.jpg", "cover.png", "folder.jpg", "folder.png", "poster.jpg", "poster.png"];
+/// Extensions of per-episode images next to episodes
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "png"];
+/// Suffixes of episode file stems naming per-episode images, like `-thumb` of Kodi thumbnails
+const IMAGE_SUFFIXES: &[&str] = &["", "-thumb"];
+
+/// Options for generating a feed from downloaded episodes
+pub struct LocalFeedOptions {
+    /// Directory with downloaded episodes, searched recursively
+    pub directory: PathBuf,
+    /// Url the directory is served at. Enclosures and images point below it.
+    pub base_url: Url,
+    /// Only include episodes recorded in this download archive
+    pub download_archive: Option<PathBuf>,
+}
+
+/// Episode file found in the download directory
+struct LocalEpisode {
+    path: PathBuf,
+    /// Path relative to the download directory
+    relative: PathBuf,
+    mime_type: &'static str,
+    info: Option<EpisodeInfo>,
+}
+
+/// Generates an rss feed for the episodes downloaded to `options.directory`
+///
+/// Metadata comes from `.info.json` and `.description.txt` files next to episodes and
+/// `description.txt` and `cover.jpg` in the podcast directory. Images written with `media_server`
+/// are used too. Episodes without an info file use their file
+/// name as title and modification time as date.
+pub fn generate_feed(options: &LocalFeedOptions) -> Result<String, Error> {
+    let mut base_url = options.base_url.clone();
+    if !base_url.path().ends_with('/') {
+        base_url.set_path(&format!("{}/", base_url.path()));
+    }
+    let archive = options.download_archive.as_deref()
+        .map(DownloadArchive::open)
+        .transpose()?;
+    let mut episodes = Vec::new();
+    find_episodes(&options.directory, &options.directory, &mut episodes)?;
+    if let Some(archive) = &archive {
+        episodes.retain(|local| match &local.info {
+            Some(info) => archive.contains(&info.podcast, &info.episode),
+            None => false,
+        });
+    }
+    let podcast = episodes.iter()
+        .find_map(|local| local.info.as_ref())
+        .map(|info| info.podcast.clone());
+    let mut items = episodes.iter()
+        .map(|local| create_item(local, &base_url))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Newest first, episodes without a date last
+    items.sort_by_key(|(date, _)| std::cmp::Reverse(*date));
+    let podcast_dir = episodes.first()
+        .and_then(|local| local.relative.parent())
+        .unwrap_or(Path::new(""));
+    let channel = create_channel(
+        podcast.as_ref(),
+        &options.directory,
+        podcast_dir,
+        &base_url,
+        items.into_iter().map(|(_, item)| item).collect(),
+    );
+    Ok(channel.to_string())
+}
+
+/// Collects episode files below `directory`
+fn find_episodes(root: &Path, directory: &Path, episodes: &mut Vec<LocalEpisode>) -> Result<(), Error> {
+    let mut entries = std::fs::read_dir(directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            find_episodes(root, &path, episodes)?;
+            continue;
+        }
+        let extension = path.extension()
+            .and_then(|x| x.to_str())
+            .map(str::to_ascii_lowercase);
+        let mime_type = MEDIA_TYPES.iter()
+            .find(|(media_extension, _)| Some(*media_extension) == extension.as_deref())
+            .map(|(_, mime_type)| *mime_type);
+        let mime_type = match mime_type {
+            Some(mime_type) => mime_type,
+            None => continue,
+        };
+        let info = match EpisodeInfo::read(&info_path(&path)) {
+            Ok(info) => Some(info),
+            Err(Error::IO(e)) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                log::warn!("Could not read metadata of {}: {}", path.display(), e);
+                None
+            },
+        };
+        episodes.push(LocalEpisode {
+            relative: path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+            path,
+            mime_type,
+            info,
+        });
+    }
+    Ok(())
+}
+
+/// Creates the feed item of an episode and returns it with its publication date for sorting
+fn create_item(local: &LocalEpisode, base_url: &Url) -> Result<(Option<chrono::DateTime<chrono::FixedOffset>>, rss::Item), Error> {
+    let metadata = std::fs::metadata(&local.path)?;
+    let episode = match &local.info {
+        Some(info) => info.episode.clone(),
+        None => Episode {
+            title: local.path.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+            pub_date: metadata.modified().ok()
+                .map(|time| chrono::DateTime::<chrono::Utc>::from(time).into()),
+            ..Default::default()
+        },
+    };
+    let description = episode.description.clone()
+        .or_else(|| std::fs::read_to_string(local.path.with_extension("description.txt")).ok());
+    let stem = local.path.file_stem().unwrap_or_default().to_string_lossy();
+    let image = IMAGE_SUFFIXES.iter()
+        .flat_map(|suffix| IMAGE_EXTENSIONS.iter().map(move |extension| (suffix, extension)))
+        .map(|(suffix, extension)| format!("{}{}.{}", stem, suffix, extension))
+        .find(|name| local.path.with_file_name(name).exists())
+        .map(|name| local_url(base_url, &local.relative.with_file_name(name)).to_string())
+        .or(episode.image.clone());
+    let item = rss::Item {
+        title: Some(episode.title.clone()),
+        description,
+        pub_date: episode.pub_date.map(|date| date.to_rfc2822()),
+        author: episode.author.clone(),
+        enclosure: Some(rss::Enclosure {
+            url: local_url(base_url, &local.relative).to_string(),
+            length: metadata.len().to_string(),
+            mime_type: local.mime_type.to_string(),
+        }),
+        guid: Some(rss::Guid {
+            value: episode.guid.clone()
+                .unwrap_or_else(|| local.relative.to_string_lossy().into_owned()),
+            permalink: false,
+        }),
+        itunes_ext: Some(ITunesItemExtension {
+            duration: episode.duration.map(format_duration),
+            episode: episode.episode_number.map(|x| x.to_string()),
+            season: episode.season.map(|x| x.to_string()),
+            episode_type: episode.episode_type.map(|x| x.to_string()),
+            explicit: episode.explicit.map(|x| x.to_string()),
+            image,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    Ok((episode.pub_date, item))
+}
+
+/// Creates the feed channel from the podcast metadata of the episodes
+///
+/// Podcast files like `description.txt` and `cover.jpg` are looked up in `directory` and then in
+/// `podcast_dir`, the directory of the first episode relative to `directory`.
+fn create_channel(
+    podcast: Option<&Podcast>,
+    directory: &Path,
+    podcast_dir: &Path,
+    base_url: &Url,
+    items: Vec<rss::Item>,
+) -> rss::Channel {
+    let find_file = |names: &[&str]| {
+        [Path::new(""), podcast_dir].into_iter()
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .find(|relative| directory.join(relative).is_file())
+    };
+    let directory_name = || {
+        std::fs::canonicalize(directory).ok()
+            .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+            .unwrap_or_default()
+    };
+    let title = podcast.map(|x| x.title.clone())
+        .unwrap_or_else(directory_name);
+    let description = find_file(&["description.txt"])
+        .and_then(|relative| std::fs::read_to_string(directory.join(relative)).ok())
+        .or_else(|| podcast.and_then(|x| x.description.clone()))
+        .unwrap_or_default();
+    let image = find_file(COVER_NAMES)
+        .map(|relative| local_url(base_url, &relative).to_string())
+        .or_else(|| podcast.and_then(|x| x.image.clone()));
+    let categories = podcast.map(|x| x.categories.clone()).unwrap_or_default();
+    rss::Channel {
+        title: title.clone(),
+        link: podcast.and_then(|x| x.link.clone())
+            .unwrap_or_else(|| base_url.to_string()),
+        description,
+        language: podcast.and_then(|x| x.language.clone()),
+        image: image.clone().map(|url| rss::Image {
+            url,
+            title: title.clone(),
+            link: base_url.to_string(),
+            ..Default::default()
+        }),
+        itunes_ext: Some(ITunesChannelExtension {
+            author: podcast.and_then(|x| x.author.clone()),
+            categories: categories.into_iter()
+                .map(|text| ITunesCategory { text, subcategory: None })
+                .collect(),
+            image,
+            ..Default::default()
+        }),
+        items,
+        ..Default::default()
+    }
+}
+
+/// Url of a file below the download directory
+fn local_url(base_url: &Url, relative: &Path) -> Url {
+    let mut url = base_url.clone();
+    if let Ok(mut segments) = url.path_segments_mut() {
+        segments.pop_if_empty();
+        segments.extend(relative.iter().map(|part| part.to_string_lossy()));
+    }
+    url
+}
+
+/// Formats seconds as `HH:MM:SS`
+fn format_duration(seconds: u64) -> String {
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
@@ -0,0 +1,222 @@
+use crate::{Podcast, Episode, Error};
+use super::{download, episode_log, escape_xml, EpisodeLog};
+use std::{
+    fmt::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use reqwest::Client;
+use serde::Serialize;
+
+/// Media server whose file layout is written next to episodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaServer {
+    /// Kodi nfo files and `poster.jpg`
+    Kodi,
+    /// Kodi style nfo files and `folder.jpg`
+    Jellyfin,
+    /// `metadata.json` and `cover.jpg`. Episode details are read from tags in the audio files, which
+    /// are always embedded for it.
+    Audiobookshelf,
+}
+
+impl MediaServer {
+    /// Names accepted by `from_str`
+    pub const VARIANTS: &'static [&'static str] = &["kodi", "jellyfin", "audiobookshelf"];
+
+    /// File name of podcast metadata
+    pub fn metadata_name(self) -> &'static str {
+        match self {
+            Self::Kodi | Self::Jellyfin => "tvshow.nfo",
+            Self::Audiobookshelf => "metadata.json",
+        }
+    }
+
+    /// File name of the podcast cover image
+    pub fn cover_name(self) -> &'static str {
+        match self {
+            Self::Kodi => "poster.jpg",
+            Self::Jellyfin => "folder.jpg",
+            Self::Audiobookshelf => "cover.jpg",
+        }
+    }
+}
+
+impl FromStr for MediaServer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "kodi" => Ok(Self::Kodi),
+            "jellyfin" => Ok(Self::Jellyfin),
+            "audiobookshelf" => Ok(Self::Audiobookshelf),
+            _ => Err(format!("Unknown media server: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for MediaServer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Kodi => "kodi",
+            Self::Jellyfin => "jellyfin",
+            Self::Audiobookshelf => "audiobookshelf",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Podcast metadata read by Audiobookshelf
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AudiobookshelfMetadata<'a> {
+    title: &'a str,
+    author: Option<&'a str>,
+    description: Option<&'a str>,
+    genres: &'a [String],
+    feed_url: Option<&'a str>,
+    image_url: Option<&'a str>,
+    language: Option<&'a str>,
+    explicit: bool,
+}
+
+/// Writes podcast metadata and cover image in the layout `server` expects
+///
+/// A podcast without an image only gets the metadata file. An existing cover image is kept.
+pub async fn write_podcast_files(
+    podcast: &Podcast,
+    server: MediaServer,
+    directory: &Path,
+    client: &Client,
+) -> Result<(), Error> {
+    let metadata = match server {
+        MediaServer::Kodi | MediaServer::Jellyfin => tvshow_nfo(podcast).or(Err(Error::StringFormat))?,
+        MediaServer::Audiobookshelf => audiobookshelf_metadata(podcast)?,
+    };
+    std::fs::write(directory.join(server.metadata_name()), metadata)
+        .or(Err(Error::WriteToFile("media server metadata")))?;
+    let cover_path = directory.join(server.cover_name());
+    if let (Some(url), false) = (&podcast.image, cover_path.exists()) {
+        let download = download::Download {
+            url,
+            path: &cover_path,
+            kind: "image",
+            name: "cover image",
+            progress: None,
+            log: &episode_log::UNBUFFERED,
+            verify: None,
+        };
+        download::download_file(client, &download).await?;
+    }
+    Ok(())
+}
+
+/// Writes the sidecar files of a downloaded episode
+///
+/// Kodi and Jellyfin get an nfo file and a thumbnail if the episode has its own image. The nfo
+/// file is always rewritten, an existing thumbnail is kept.
+/// Audiobookshelf has no episode sidecars and reads the tags embedded for it instead.
+pub async fn write_episode_files(
+    podcast: &Podcast,
+    episode: &Episode,
+    audio_path: &Path,
+    server: MediaServer,
+    client: &Client,
+    episode_log: &EpisodeLog,
+) -> Result<(), Error> {
+    if server == MediaServer::Audiobookshelf {
+        return Ok(());
+    }
+    let nfo = episode_nfo(podcast, episode).or(Err(Error::StringFormat))?;
+    std::fs::write(audio_path.with_extension("nfo"), nfo)
+        .or(Err(Error::WriteToFile("nfo")))?;
+    let image = episode.image.as_ref()
+        .filter(|image| Some(*image) != podcast.image.as_ref());
+    let thumb_path = thumb_path(audio_path);
+    if let (Some(url), false) = (image, thumb_path.exists()) {
+        let download = download::Download {
+            url,
+            path: &thumb_path,
+            kind: "image",
+            name: &episode.title,
+            progress: None,
+            log: episode_log,
+            verify: None,
+        };
+        download::download_file(client, &download).await?;
+    }
+    Ok(())
+}
+
+/// Path of the episode thumbnail, `<name>-thumb.jpg`
+fn thumb_path(audio_path: &Path) -> PathBuf {
+    let mut name = audio_path.file_stem().unwrap_or_default().to_os_string();
+    name.push("-thumb.jpg");
+    audio_path.with_file_name(name)
+}
+
+/// Writes `<name>value</name>` on its own line if `value` is set
+fn element(nfo: &mut String, name: &str, value: Option<&str>) -> fmt::Result {
+    match value {
+        Some(value) => writeln!(nfo, "  <{0}>{1}</{0}>", name, escape_xml(value)),
+        None => Ok(()),
+    }
+}
+
+fn tvshow_nfo(podcast: &Podcast) -> Result<String, fmt::Error> {
+    let mut nfo = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<tvshow>\n");
+    element(&mut nfo, "title", Some(&podcast.title))?;
+    element(&mut nfo, "plot", podcast.description.as_deref())?;
+    element(&mut nfo, "studio", podcast.author.as_deref())?;
+    for category in &podcast.categories {
+        element(&mut nfo, "genre", Some(category))?;
+    }
+    if let Some(image) = &podcast.image {
+        writeln!(nfo, "  <thumb aspect=\"poster\">{}</thumb>", escape_xml(image))?;
+    }
+    if let Some(id) = podcast.guid.as_ref().or(podcast.feed_url.as_ref()) {
+        writeln!(nfo, "  <uniqueid type=\"podcast\" default=\"true\">{}</uniqueid>", escape_xml(id))?;
+    }
+    nfo.push_str("</tvshow>\n");
+    Ok(nfo)
+}
+
+fn episode_nfo(podcast: &Podcast, episode: &Episode) -> Result<String, fmt::Error> {
+    let mut nfo = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<episodedetails>\n");
+    element(&mut nfo, "title", Some(&episode.title))?;
+    element(&mut nfo, "showtitle", Some(&podcast.title))?;
+    // Episodes are matched by season and episode, so feeds without them count as one season
+    let season = episode.season.unwrap_or(1);
+    element(&mut nfo, "season", Some(&season.to_string()))?;
+    let number = episode.episode_number.or(episode.index).map(|x| x.to_string());
+    element(&mut nfo, "episode", number.as_deref())?;
+    element(&mut nfo, "plot", episode.description.as_deref())?;
+    let aired = episode.pub_date.map(|date| date.format("%Y-%m-%d").to_string());
+    element(&mut nfo, "aired", aired.as_deref())?;
+    // Runtime is in whole minutes
+    let runtime = episode.duration.map(|duration| duration.div_ceil(60).to_string());
+    element(&mut nfo, "runtime", runtime.as_deref())?;
+    element(&mut nfo, "credits", episode.author.as_ref().or(podcast.author.as_ref()).map(String::as_str))?;
+    if let Some(guid) = &episode.guid {
+        writeln!(nfo, "  <uniqueid type=\"podcast\" default=\"true\">{}</uniqueid>", escape_xml(guid))?;
+    }
+    nfo.push_str("</episodedetails>\n");
+    Ok(nfo)
+}
+
+fn audiobookshelf_metadata(podcast: &Podcast) -> Result<String, Error> {
+    let metadata = AudiobookshelfMetadata {
+        title: &podcast.title,
+        author: podcast.author.as_deref(),
+        description: podcast.description.as_deref(),
+        genres: &podcast.categories,
+        feed_url: podcast.feed_url.as_deref(),
+        image_url: podcast.image.as_deref(),
+        language: podcast.language.as_deref(),
+        // The channel explicit flag is only kept as the fallback of every episode
+        explicit: !podcast.episodes.is_empty()
+            && podcast.episodes.iter().all(|episode| episode.explicit == Some(true)),
+    };
+    serde_json::to_string_pretty(&metadata)
+        .or(Err(Error::WriteToFile("media server metadata")))
+}
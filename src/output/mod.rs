@@ -1,64 +1,340 @@
-/// Iterator over episode
-mod episode_iterator;
+/// Record of downloaded episodes
+mod archive;
+/// Streaming downloads to disk
+mod download;
+/// Keeping log messages of concurrent downloads together
+mod episode_log;
+/// Selecting episodes by their metadata
+mod episode_filter;
+/// Json metadata written next to episodes
+mod info;
+/// Sidecar files read by media servers
+mod media_server;
 /// Module for formatting episode as strings
 mod formatting;
+/// Rss feeds for downloaded episodes
+mod local_feed;
+/// Reporting download progress
+mod progress;
+/// Overwrite and collision policies
+mod policy;
+/// Deciding what a download would do without writing anything
+mod plan;
+/// Playlists of downloaded episodes
+mod playlist;
+/// Choosing which episodes to download
+mod selection;
+/// Embedding metadata in audio files
+mod tagging;
 /// Struct for storing write options
 mod write_options;
 
 use crate::{
     error::Error,
-    logging,
     Podcast, Episode,
 };
 use std::{
     path::{PathBuf, Path},
-    str::FromStr,
-    io::Write,
+    io::{Read, Write},
 };
+use futures::{stream, StreamExt};
 use reqwest::Client;
-use episode_iterator::EpisodeIterator;
+pub use archive::DownloadArchive;
+use episode_log::EpisodeLog;
 pub use write_options::WriteOptions;
+pub use episode_filter::EpisodeFilter;
+pub use info::{EpisodeInfo, info_path};
+pub use selection::{EpisodeRanges, select_episodes};
+pub use formatting::{Sanitize, episode_path};
+pub use policy::{Overwrite, Collision, Action, SkipReason, AssignedPath, assign_paths};
+pub use plan::{PlannedEpisode, plan_podcast};
+pub use playlist::PlaylistFormat;
+pub use media_server::MediaServer;
+pub use local_feed::{LocalFeedOptions, generate_feed};
+pub use progress::{Progress, DownloadProgress};
+pub use download::{ResponseAction, response_action, is_media};
+
+/// How many times an episode failing verification is downloaded again
+const VERIFY_RETRIES: usize = 2;
 
 struct WriteData<'a> {
     podcast: &'a Podcast,
     client: Client,
     options: &'a WriteOptions,
+    archive: Option<DownloadArchive>,
+    /// Cover image embedded in episodes, downloaded with the first episode that is embedded into
+    cover: tokio::sync::OnceCell<Option<tagging::Cover>>,
 }
 
 /// Downloads all episodes in `podcast` based on `options`
+///
+/// Up to `options.jobs` episodes are downloaded at the same time. Results are logged in the same
+/// order as the episodes.
 pub async fn download_podcast(podcast: &Podcast, options: &WriteOptions) -> Result<(), Error> {
+    options.validate()?;
     let write_data = WriteData {
         podcast, options,
-        client: Client::new()
+        client: Client::new(),
+        cover: tokio::sync::OnceCell::new(),
+        archive: options.download_archive.as_deref()
+            .map(DownloadArchive::open)
+            .transpose()?,
     };
-    let episodes = EpisodeIterator::new(podcast, options);
-    for episode in episodes {
-        let result = download_episode(episode, &write_data).await;
-        logging::print_download_status(&result);
+    let episodes = select_episodes(podcast, options);
+    let paths = policy::assign_paths(podcast, &episodes, options);
+    // Resolved before downloading, so a podcast directory that can not be formatted is reported
+    // up front and does not fail a run that downloaded every episode
+    let media_server_dir = options.media_server.zip(podcast.episodes.first())
+        .and_then(|(server, first)| match media_server_directory(podcast, first, options) {
+            Ok(directory) => Some((server, directory)),
+            Err(e) => {
+                log::error!("Not writing {} podcast files: {}", server, e);
+                None
+            },
+        });
+    let playlist_entries: Vec<(&Episode, PathBuf)> = episodes.iter()
+        .zip(&paths)
+        .filter_map(|(episode, path)| Some((*episode, path.as_ref().ok()?.path.clone())))
+        .collect();
+    let progress = options.progress.as_deref();
+    if let Some(progress) = progress {
+        progress.start_batch(episodes.len());
+    }
+    let buffered = options.jobs > 1;
+    let write_data = &write_data;
+    let mut downloads = stream::iter(episodes.into_iter().zip(paths))
+        .map(|(episode, path)| async move {
+            let episode_log = EpisodeLog::new(buffered);
+            if let Ok(AssignedPath { path, collision: Some(collision) }) = &path {
+                episode_log.info(format!("Saving {} as {} (collision: {})", episode.title, path.display(), collision));
+            }
+            let path = path.map(|assigned| assigned.path);
+            let audio_path = path.as_ref().ok().cloned();
+            let result = download_episode(episode, path, write_data, &episode_log).await;
+            let skipped = matches!(result, Err(Error::FileExists(_) | Error::Unchanged(_) | Error::InArchive(_)));
+            if let (true, Some(audio_path)) = (skipped, audio_path) {
+                write_skipped_episode_files(episode, &audio_path, write_data, &episode_log).await;
+            }
+            (episode_log, result)
+        })
+        .buffered(options.jobs.max(1));
+    while let Some((episode_log, result)) = downloads.next().await {
+        episode_log.flush();
+        log_download_status(&result);
+        if let Some(progress) = progress {
+            progress.episode_done();
+        }
+    }
+    if let Some(progress) = progress {
+        progress.finish_batch();
+    }
+    if let Some((server, directory)) = media_server_dir {
+        let result = async {
+            std::fs::create_dir_all(&directory)?;
+            media_server::write_podcast_files(podcast, server, &directory, &write_data.client).await
+        }.await;
+        if let Err(e) = result {
+            log::error!("Could not write {} podcast files: {}", server, e);
+        }
+    }
+    if let Some(format) = options.write_playlist {
+        if let Some(path) = playlist::write_playlist(podcast, &playlist_entries, format, options)? {
+            log::info!("Wrote playlist {}", path.display());
+        }
     }
     Ok(())
 }
 
+/// Directory of the media server podcast files, the folder of the first episode like `download_image`
+fn media_server_directory(podcast: &Podcast, first: &Episode, options: &WriteOptions) -> Result<PathBuf, Error> {
+    let path = formatting::episode_path(podcast, first, options)?;
+    Ok(path.parent().ok_or(Error::Path)?.to_path_buf())
+}
+
+/// Downloads cover image for embedding in episodes
+async fn download_cover(client: &Client, url: &str) -> Option<tagging::Cover> {
+    let result = async {
+        client.get(url).send().await?
+            .error_for_status()?
+            .bytes().await
+    }.await;
+    match result {
+        Ok(data) => Some(tagging::Cover::new(data.to_vec())),
+        Err(e) => {
+            log::warn!("Could not download cover image: {}", e);
+            None
+        }
+    }
+}
+
+/// Logs the result of downloading a single episode
+fn log_download_status(result: &Result<(), Error>) {
+    if let Err(error) = result {
+        match error {
+            Error::FileExists(title) => log::info!("Skipping {} (file exists, overwrite: never)", title),
+            Error::InArchive(title) => log::info!("Skipping {} (in download archive)", title),
+            Error::Unchanged(title) => log::info!("Skipping {} (unchanged, overwrite: if-different)", title),
+            Error::PathCollision(title) => log::info!("Skipping {} (same path as another episode, collision: skip)", title),
+            e => log::error!("{}", e),
+        }
+    }
+}
+
 /// Writes a single episode to disk
-async fn download_episode<'a>(episode: &Episode, write_data: &WriteData<'a>) -> Result<(), Error> {
-    let audio_path = create_path(episode, write_data)?;
-    log::info!("Downloading {}", episode.title);
-    download_episode_audio(episode, &audio_path, &write_data.client).await?;
+async fn download_episode<'a>(
+    episode: &Episode,
+    path: Result<PathBuf, Error>,
+    write_data: &WriteData<'a>,
+    episode_log: &EpisodeLog,
+) -> Result<(), Error> {
+    if let Some(archive) = &write_data.archive {
+        if archive.contains(write_data.podcast, episode) {
+            return Err(Error::InArchive(episode.title.clone()));
+        }
+    }
+    let audio_path = path?;
+    let overwrite = write_data.options.overwrite;
+    let remote_size = match policy::needs_remote_size(&audio_path, overwrite) {
+        true => remote_size(episode, &write_data.client, episode_log).await,
+        false => None,
+    };
+    let action = policy::existing_action(&audio_path, overwrite, remote_size);
+    match &action {
+        Action::Skip(SkipReason::Unchanged) => return Err(Error::Unchanged(episode.title.clone())),
+        Action::Skip(_) => return Err(Error::FileExists(episode.title.clone())),
+        Action::Overwrite => episode_log.info(format!("Overwriting {} (overwrite: {})", episode.title, overwrite)),
+        _ => (),
+    }
+    create_parent(&audio_path)?;
+    episode_log.info(format!("Downloading {}", episode.title));
+    if action == Action::Compare {
+        // Downloaded next to the existing file and only moved over it if different
+        let new_path = new_path(&audio_path);
+        download_episode_audio(episode, &new_path, write_data, episode_log).await?;
+        if files_equal(&new_path, &audio_path)? {
+            std::fs::remove_file(&new_path)?;
+            return Err(Error::Unchanged(episode.title.clone()));
+        }
+        episode_log.info(format!("Overwriting {} (content differs, overwrite: if-different)", episode.title));
+        std::fs::rename(&new_path, &audio_path)?;
+    } else {
+        download_episode_audio(episode, &audio_path, write_data, episode_log).await?;
+    }
+    if write_data.options.embeds_metadata() {
+        // Fetched here so runs skipping every episode make no request for it
+        let cover = write_data.cover.get_or_init(|| async {
+            match &write_data.podcast.image {
+                Some(url) => download_cover(&write_data.client, url).await,
+                None => None,
+            }
+        }).await;
+        tagging::embed_metadata(&audio_path, write_data.podcast, episode, cover.as_ref())?;
+    }
+    if let Some(archive) = &write_data.archive {
+        archive.record(write_data.podcast, episode)?;
+    }
     if write_data.options.write_episode_description {
         write_episode_description(episode, &audio_path)?;
     }
+    if write_data.options.write_info_json {
+        EpisodeInfo::new(write_data.podcast, episode).write(&info_path(&audio_path))?;
+    }
+    if let Some(server) = write_data.options.media_server {
+        media_server::write_episode_files(write_data.podcast, episode, &audio_path, server, &write_data.client, episode_log).await?;
+    }
     Ok(())
 }
 
+/// Writes the media server sidecars of an episode skipped because it is already downloaded
+///
+/// Failures are logged as warnings, so the episode is still reported as skipped.
+async fn write_skipped_episode_files(episode: &Episode, audio_path: &Path, write_data: &WriteData<'_>, episode_log: &EpisodeLog) {
+    let server = match write_data.options.media_server {
+        Some(server) if audio_path.exists() => server,
+        _ => return,
+    };
+    let result = media_server::write_episode_files(write_data.podcast, episode, audio_path, server, &write_data.client, episode_log).await;
+    if let Err(e) = result {
+        episode_log.log(log::Level::Warn, format!("Could not write {} files of {}: {}", server, episode.title, e));
+    }
+}
+
+/// Size of episode audio from a HEAD request, or the enclosure length if the server does not send it
+async fn remote_size(episode: &Episode, client: &Client, episode_log: &EpisodeLog) -> Option<u64> {
+    let content_length = client.head(&episode.link).send().await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| episode_log.debug(format!("HEAD request for {} failed: {}", episode.title, e)))
+        .ok()
+        .and_then(|response| {
+            response.headers()
+                .get(reqwest::header::CONTENT_LENGTH)?
+                .to_str().ok()?
+                .parse().ok()
+        });
+    content_length.or(episode.length.filter(|length| *length > 0))
+}
+
+/// Path an episode is downloaded to before being compared with the existing file at `path`
+fn new_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name()
+        .unwrap_or_default()
+        .to_os_string();
+    name.push(".new");
+    path.with_file_name(name)
+}
+
+/// Compares the contents of two files
+fn files_equal(a: &Path, b: &Path) -> Result<bool, Error> {
+    if std::fs::metadata(a)?.len() != std::fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    let mut a = std::io::BufReader::new(std::fs::File::open(a)?);
+    let mut b = std::io::BufReader::new(std::fs::File::open(b)?);
+    let mut buffer_a = [0; 8192];
+    let mut buffer_b = [0; 8192];
+    loop {
+        let read = a.read(&mut buffer_a)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buffer_b[..read])?;
+        if buffer_a[..read] != buffer_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
 /// Download episode audio and write it to file
-async fn download_episode_audio(episode: &Episode, path: &PathBuf, client: &Client) -> Result<(), Error> {
-    let data = client.get(&episode.link)
-        .send().await?
-        .bytes().await?;
-    let mut file = std::fs::File::create(&path)?;
-    file.write_all(&data)
-        .or(Err(Error::WriteToFile("audio")))?;
-    Ok(())
+///
+/// Downloads failing verification are retried up to `VERIFY_RETRIES` times.
+async fn download_episode_audio(
+    episode: &Episode,
+    path: &Path,
+    write_data: &WriteData<'_>,
+    episode_log: &EpisodeLog,
+) -> Result<(), Error> {
+    let mut attempt = 0;
+    loop {
+        let download = download::Download {
+            url: &episode.link,
+            path,
+            kind: "audio",
+            name: &episode.title,
+            progress: write_data.options.progress.as_deref(),
+            log: episode_log,
+            verify: (!write_data.options.no_verify).then_some(download::Verify {
+                enclosure_length: episode.length,
+                enclosure_type: episode.content_type.as_deref(),
+            }),
+        };
+        match download::download_file(&write_data.client, &download).await {
+            Err(Error::Verification(title, reason)) if attempt < VERIFY_RETRIES => {
+                attempt += 1;
+                episode_log.log(log::Level::Warn, format!("Retrying {} ({})", title, reason));
+            },
+            result => return result,
+        }
+    }
 }
 
 /// Write episode description to file
@@ -74,27 +350,27 @@ fn write_episode_description(episode: &Episode, audio_path: &Path) -> Result<(),
     }
 }
 
-/// Creates a pathbuf from string and creates its parent directory if it does not exist
-fn create_path(episode: &Episode, write_data: &WriteData) -> Result<PathBuf, Error> {
-    // Create path
-    let formatted = formatting::format_episode(write_data.podcast, episode, &write_data.options)?;
-    let pathbuf = PathBuf::from_str(&formatted).unwrap();
-    // Check if file exists
-    if pathbuf.exists() {
-        return Err(Error::FileExists(episode.title.clone()));
-    }
-    // Check if directory exists
-    if !pathbuf.parent().unwrap().exists() {
-        std::fs::create_dir_all(pathbuf.parent().unwrap())?;
+/// Escapes text for use in xml elements and attributes
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Creates the parent directory of `path` if it does not exist
+fn create_parent(path: &Path) -> Result<(), Error> {
+    let parent = path.parent().ok_or(Error::Path)?;
+    if !parent.exists() {
+        std::fs::create_dir_all(parent)?;
     }
-    return Ok(pathbuf);
+    Ok(())
 }
 
 /// Creates a path to a file in the same directory as the first episode
 fn create_non_episode_path(podcast: &Podcast, file_name: &str, options: &WriteOptions) -> Result<PathBuf, Error> {
-    let formatted = formatting::format_episode(podcast, &podcast.episodes[0], options)?;
-    PathBuf::from_str(&formatted).ok()
-        .and_then(|path| Some(PathBuf::from(path.parent()?)))
+    let path = formatting::episode_path(podcast, &podcast.episodes[0], options)?;
+    path.parent()
         .map(|parent| parent.join(file_name))
         .ok_or(Error::Path)
 }
@@ -102,14 +378,17 @@ fn create_non_episode_path(podcast: &Podcast, file_name: &str, options: &WriteOp
 /// Downloads cover image of podcast in the same folder as the first episode as cover.jpg
 pub async fn download_image(podcast: &Podcast, options: &WriteOptions) -> Result<(), Error> {
     if let Some(url) = &podcast.image {
-        // Download
-        let image = reqwest::get(url).await?
-            .bytes().await?;
-        // Write
         let cover_path = create_non_episode_path(podcast, "cover.jpg", options)?;
-        let mut file = std::fs::File::create(&cover_path)?;
-        file.write_all(&image)?;
-        Ok(())
+        let download = download::Download {
+            url,
+            path: &cover_path,
+            kind: "image",
+            name: "cover image",
+            progress: None,
+            log: &episode_log::UNBUFFERED,
+            verify: None,
+        };
+        download::download_file(&Client::new(), &download).await
     // Image missing from podcast
     } else {
         Err(Error::ValueMissing{ value: "image", from: "podcast" })
@@ -0,0 +1,82 @@
+use crate::{Podcast, Episode, Error};
+use super::{
+    policy::{self, Action, AssignedPath, SkipReason},
+    select_episodes, DownloadArchive, WriteOptions,
+};
+use std::path::PathBuf;
+use futures::{stream, StreamExt};
+use reqwest::Client;
+
+/// What downloading an episode would do
+#[derive(Debug)]
+pub struct PlannedEpisode<'a> {
+    pub episode: &'a Episode,
+    /// Path the episode would be written to, if it could be formatted
+    pub path: Option<PathBuf>,
+    pub action: Action,
+    /// Size of the episode from a HEAD request or the enclosure, if sizes were requested
+    pub size: Option<u64>,
+}
+
+/// Decides what `download_podcast` would do with every selected episode without writing anything
+///
+/// Runs selection, path formatting, collision handling and the overwrite policy. If
+/// `request_sizes` is set, a HEAD request is sent for every episode that would be downloaded.
+/// Otherwise, HEAD requests are only sent for existing files under `Overwrite::IfDifferent`, the
+/// same as when downloading. No audio is downloaded either way.
+pub async fn plan_podcast<'a>(
+    podcast: &'a Podcast,
+    options: &WriteOptions,
+    request_sizes: bool,
+) -> Result<Vec<PlannedEpisode<'a>>, Error> {
+    options.validate()?;
+    let client = Client::new();
+    let archive = options.download_archive.as_deref()
+        .map(DownloadArchive::open)
+        .transpose()?;
+    let episodes = select_episodes(podcast, options);
+    let paths = policy::assign_paths(podcast, &episodes, options);
+    let planned = stream::iter(episodes.into_iter().zip(paths))
+        .map(|(episode, path)| {
+            if let Ok(AssignedPath { path, collision: Some(collision) }) = &path {
+                log::info!("Saving {} as {} (collision: {})", episode.title, path.display(), collision);
+            }
+            let path = path.map(|assigned| assigned.path);
+            let in_archive = archive.as_ref()
+                .is_some_and(|archive| archive.contains(podcast, episode));
+            plan_episode(episode, path, in_archive, options, &client, request_sizes)
+        })
+        .buffered(options.jobs.max(1))
+        .collect()
+        .await;
+    Ok(planned)
+}
+
+/// Decides what downloading a single episode would do
+async fn plan_episode<'a>(
+    episode: &'a Episode,
+    path: Result<PathBuf, Error>,
+    in_archive: bool,
+    options: &WriteOptions,
+    client: &Client,
+    request_sizes: bool,
+) -> PlannedEpisode<'a> {
+    let (path, action) = match path {
+        _ if in_archive => (path.ok(), Action::Skip(SkipReason::InArchive)),
+        Err(Error::PathCollision(_)) => (None, Action::Skip(SkipReason::Collision)),
+        Err(e) => (None, Action::Fail(e.to_string())),
+        Ok(path) => (Some(path), Action::Download),
+    };
+    let needs_size = request_sizes
+        || path.as_deref().is_some_and(|path| policy::needs_remote_size(path, options.overwrite));
+    let size = match (&action, needs_size) {
+        (Action::Download, true) => super::remote_size(episode, client, &super::episode_log::UNBUFFERED).await,
+        _ => None,
+    };
+    let action = match (&path, action) {
+        (Some(path), Action::Download) => policy::existing_action(path, options.overwrite, size),
+        (_, action) => action,
+    };
+    let size = size.filter(|_| request_sizes && matches!(action, Action::Download | Action::Overwrite | Action::Compare));
+    PlannedEpisode { episode, path, action, size }
+}
@@ -0,0 +1,188 @@
+use crate::{Podcast, Episode, Error};
+use super::{escape_xml, formatting, WriteOptions};
+use std::{
+    fmt::{self, Write},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+/// File format of playlists written next to episodes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    /// Extended M3U with utf-8 encoding
+    #[default]
+    M3u8,
+    /// PLS, as read by older hardware players
+    Pls,
+    /// XML Shareable Playlist Format
+    Xspf,
+}
+
+impl PlaylistFormat {
+    /// Names accepted by `from_str`
+    pub const VARIANTS: &'static [&'static str] = &["m3u8", "pls", "xspf"];
+
+    /// Extension of playlist files
+    fn extension(self) -> &'static str {
+        match self {
+            Self::M3u8 => "m3u8",
+            Self::Pls => "pls",
+            Self::Xspf => "xspf",
+        }
+    }
+}
+
+impl FromStr for PlaylistFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "m3u8" => Ok(Self::M3u8),
+            "pls" => Ok(Self::Pls),
+            "xspf" => Ok(Self::Xspf),
+            _ => Err(format!("Unknown playlist format: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for PlaylistFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+/// Episode in a playlist with its path relative to the playlist
+struct Entry<'a> {
+    episode: &'a Episode,
+    path: String,
+}
+
+/// Writes a playlist of the episode files that exist, in the order of `episodes`
+///
+/// The playlist is named after the podcast and written to the deepest directory containing all
+/// episodes, so it lists both new downloads and files from earlier runs. Returns the path of the
+/// playlist, or `None` if no episode file exists.
+pub fn write_playlist(
+    podcast: &Podcast,
+    episodes: &[(&Episode, PathBuf)],
+    format: PlaylistFormat,
+    options: &WriteOptions,
+) -> Result<Option<PathBuf>, Error> {
+    let existing: Vec<_> = episodes.iter()
+        .filter(|(_, path)| path.is_file())
+        .collect();
+    let directory = match common_directory(existing.iter().map(|(_, path)| path.as_path())) {
+        Some(directory) => directory,
+        None => return Ok(None),
+    };
+    let entries: Vec<_> = existing.iter()
+        .map(|(episode, path)| Entry {
+            episode,
+            path: relative_path(path, &directory),
+        })
+        .collect();
+    let playlist = match format {
+        PlaylistFormat::M3u8 => m3u8(podcast, &entries),
+        PlaylistFormat::Pls => pls(&entries),
+        PlaylistFormat::Xspf => xspf(podcast, &entries),
+    }.or(Err(Error::StringFormat))?;
+    let name = formatting::sanitize_value(&podcast.title, options.sanitize);
+    let name = if name.is_empty() { "playlist".to_string() } else { name };
+    let path = directory.join(format!("{}.{}", name, format.extension()));
+    std::fs::write(&path, playlist)
+        .or(Err(Error::WriteToFile("playlist")))?;
+    Ok(Some(path))
+}
+
+/// Deepest directory containing all `paths`
+fn common_directory<'a>(mut paths: impl Iterator<Item = &'a Path>) -> Option<PathBuf> {
+    let mut common = paths.next()?.parent()?.to_path_buf();
+    for path in paths {
+        while !path.starts_with(&common) {
+            if !common.pop() {
+                break;
+            }
+        }
+    }
+    Some(common)
+}
+
+/// `path` relative to `directory` with `/` as separator
+fn relative_path(path: &Path, directory: &Path) -> String {
+    path.strip_prefix(directory).unwrap_or(path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Duration in seconds as used by playlists, `-1` if unknown
+fn duration(episode: &Episode) -> i64 {
+    episode.duration.map_or(-1, |duration| duration as i64)
+}
+
+/// Titles and comments in playlists have to stay on one line
+fn single_line(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn m3u8(podcast: &Podcast, entries: &[Entry]) -> Result<String, fmt::Error> {
+    let mut playlist = String::from("#EXTM3U\n");
+    writeln!(playlist, "#PLAYLIST:{}", single_line(&podcast.title))?;
+    for entry in entries {
+        writeln!(playlist, "#EXTINF:{},{}", duration(entry.episode), single_line(&entry.episode.title))?;
+        writeln!(playlist, "{}", entry.path)?;
+    }
+    Ok(playlist)
+}
+
+fn pls(entries: &[Entry]) -> Result<String, fmt::Error> {
+    let mut playlist = String::from("[playlist]\n");
+    for (number, entry) in (1..).zip(entries) {
+        writeln!(playlist, "File{}={}", number, entry.path)?;
+        writeln!(playlist, "Title{}={}", number, single_line(&entry.episode.title))?;
+        writeln!(playlist, "Length{}={}", number, duration(entry.episode))?;
+    }
+    writeln!(playlist, "NumberOfEntries={}", entries.len())?;
+    writeln!(playlist, "Version=2")?;
+    Ok(playlist)
+}
+
+fn xspf(podcast: &Podcast, entries: &[Entry]) -> Result<String, fmt::Error> {
+    let mut playlist = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    writeln!(playlist, "<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">")?;
+    writeln!(playlist, "  <title>{}</title>", escape_xml(&podcast.title))?;
+    writeln!(playlist, "  <trackList>")?;
+    for entry in entries {
+        writeln!(playlist, "    <track>")?;
+        writeln!(playlist, "      <location>{}</location>", escape_xml(&encode_uri(&entry.path)))?;
+        writeln!(playlist, "      <title>{}</title>", escape_xml(&entry.episode.title))?;
+        writeln!(playlist, "      <album>{}</album>", escape_xml(&podcast.title))?;
+        if let Some(author) = entry.episode.author.as_ref().or(podcast.author.as_ref()) {
+            writeln!(playlist, "      <creator>{}</creator>", escape_xml(author))?;
+        }
+        if let Some(duration) = entry.episode.duration {
+            // Xspf durations are in milliseconds
+            writeln!(playlist, "      <duration>{}</duration>", duration * 1000)?;
+        }
+        writeln!(playlist, "    </track>")?;
+    }
+    writeln!(playlist, "  </trackList>")?;
+    writeln!(playlist, "</playlist>")?;
+    Ok(playlist)
+}
+
+/// Percent-encodes a relative path for use as uri reference
+fn encode_uri(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
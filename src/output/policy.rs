@@ -0,0 +1,248 @@
+use crate::{Podcast, Episode, Error};
+use super::{formatting, WriteOptions};
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// What to do when the file of an episode already exists
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overwrite {
+    /// Keep the existing file and skip the episode
+    #[default]
+    Never,
+    /// Download the episode again and replace the file
+    Always,
+    /// Replace the file if its size differs from the file on the server. If the server does not
+    /// report a size, the episode is downloaded and compared with the existing file.
+    IfDifferent,
+}
+
+impl Overwrite {
+    /// Names accepted by `from_str`
+    pub const VARIANTS: &'static [&'static str] = &["never", "always", "if-different"];
+}
+
+impl FromStr for Overwrite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "never" => Ok(Self::Never),
+            "always" => Ok(Self::Always),
+            "if-different" => Ok(Self::IfDifferent),
+            _ => Err(format!("Unknown overwrite policy: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Overwrite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Never => "never",
+            Self::Always => "always",
+            Self::IfDifferent => "if-different",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What to do when several episodes in a run are formatted to the same path
+///
+/// The oldest episode keeps the path, so file names stay the same when new episodes are published.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+    /// Skip the newer episodes
+    #[default]
+    Skip,
+    /// Append the episode index, like `Title (12).mp3`
+    Index,
+    /// Append the start of the episode guid, like `Title [a1b2c3d4].mp3`. Falls back to `Index`.
+    Guid,
+    /// Append the publication date, like `Title (2023-01-02).mp3`. Falls back to `Index`.
+    Date,
+}
+
+impl Collision {
+    /// Names accepted by `from_str`
+    pub const VARIANTS: &'static [&'static str] = &["skip", "index", "guid", "date"];
+
+    /// Text appended to the file name of `episode` and the strategy actually used
+    fn suffix(self, episode: &Episode) -> Option<(String, Collision)> {
+        let guid = episode.guid.as_deref()
+            .map(|guid| guid.chars().filter(char::is_ascii_alphanumeric).take(8).collect::<String>())
+            .filter(|guid| !guid.is_empty());
+        match (self, guid, episode.pub_date) {
+            (Self::Skip, _, _) => None,
+            (Self::Guid, Some(guid), _) => Some((format!(" [{}]", guid), Self::Guid)),
+            (Self::Date, _, Some(date)) => Some((format!(" ({})", date.format("%Y-%m-%d")), Self::Date)),
+            _ => episode.index.map(|index| (format!(" ({})", index), Self::Index)),
+        }
+    }
+}
+
+impl FromStr for Collision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "skip" => Ok(Self::Skip),
+            "index" => Ok(Self::Index),
+            "guid" => Ok(Self::Guid),
+            "date" => Ok(Self::Date),
+            _ => Err(format!("Unknown collision strategy: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Skip => "skip",
+            Self::Index => "index",
+            Self::Guid => "guid",
+            Self::Date => "date",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Path assigned to an episode by `assign_paths`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssignedPath {
+    pub path: PathBuf,
+    /// Strategy that renamed the path because another episode has the same one
+    pub collision: Option<Collision>,
+}
+
+/// Formats the path of every episode, handling episodes with the same path with `options.collision`
+///
+/// Collisions are resolved over all episodes of `podcast`, not only `episodes`, so an episode
+/// keeps its path however many episodes are selected. The oldest episode keeps the plain path.
+/// Results are in the same order as `episodes`.
+pub fn assign_paths(podcast: &Podcast, episodes: &[&Episode], options: &WriteOptions) -> Vec<Result<AssignedPath, Error>> {
+    // Selected episodes are usually borrowed from `podcast`, others are added at the end
+    let mut all: Vec<&Episode> = podcast.episodes.iter().collect();
+    let positions: Vec<usize> = episodes.iter()
+        .map(|episode| match all.iter().position(|other| std::ptr::eq(*other, *episode)) {
+            Some(position) => position,
+            None => {
+                all.push(*episode);
+                all.len() - 1
+            },
+        })
+        .collect();
+    let mut paths: Vec<Result<AssignedPath, Error>> = all.iter()
+        .map(|episode| formatting::episode_path(podcast, episode, options))
+        .map(|path| path.map(|path| AssignedPath { path, collision: None }))
+        .collect();
+    let mut order: Vec<usize> = (0..all.len()).collect();
+    order.sort_by_key(|&i| all[i].index);
+    let mut taken = HashSet::new();
+    for i in order {
+        let path = match &paths[i] {
+            Ok(assigned) => assigned.path.clone(),
+            Err(_) => continue,
+        };
+        if taken.insert(path.clone()) {
+            continue;
+        }
+        let episode = all[i];
+        let renamed = options.collision.suffix(episode)
+            .map(|(suffix, applied)| (with_suffix(&path, &suffix), applied))
+            .filter(|(renamed, _)| taken.insert(renamed.clone()));
+        paths[i] = match renamed {
+            Some((renamed, applied)) => Ok(AssignedPath { path: renamed, collision: Some(applied) }),
+            None => Err(Error::PathCollision(episode.title.clone())),
+        };
+    }
+    let mut paths: Vec<Option<Result<AssignedPath, Error>>> = paths.into_iter().map(Some).collect();
+    positions.into_iter()
+        .map(|position| paths[position].take().unwrap_or_else(|| Err(Error::PathCollision(all[position].title.clone()))))
+        .collect()
+}
+
+/// Inserts `suffix` between the file name and extension of `path`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+/// What happens to an episode when downloading
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Download to a new file
+    Download,
+    /// Download and replace the existing file
+    Overwrite,
+    /// Download and replace the existing file if the contents differ
+    Compare,
+    /// Leave the episode out
+    Skip(SkipReason),
+    /// The episode cannot be downloaded, like when its path is invalid
+    Fail(String),
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Download => f.write_str("download"),
+            Self::Overwrite => f.write_str("overwrite"),
+            Self::Compare => f.write_str("compare"),
+            Self::Skip(reason) => write!(f, "skip ({})", reason),
+            Self::Fail(_) => f.write_str("error"),
+        }
+    }
+}
+
+/// Why an episode is left out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The file exists and is not overwritten
+    Exists,
+    /// The file has the same size as on the server
+    Unchanged,
+    /// The episode is in the download archive
+    InArchive,
+    /// Another episode has the same path
+    Collision,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Self::Exists => "exists",
+            Self::Unchanged => "unchanged",
+            Self::InArchive => "in archive",
+            Self::Collision => "collision",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// Returns true if `overwrite` needs the size of the episode on the server to decide about `path`
+pub fn needs_remote_size(path: &Path, overwrite: Overwrite) -> bool {
+    overwrite == Overwrite::IfDifferent && path.exists()
+}
+
+/// Applies `overwrite` to the file at `path`, given the size of the episode on the server
+pub fn existing_action(path: &Path, overwrite: Overwrite, remote_size: Option<u64>) -> Action {
+    let existing_size = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return Action::Download,
+    };
+    match (overwrite, remote_size) {
+        (Overwrite::Never, _) => Action::Skip(SkipReason::Exists),
+        (Overwrite::Always, _) => Action::Overwrite,
+        (Overwrite::IfDifferent, Some(size)) if size == existing_size => Action::Skip(SkipReason::Unchanged),
+        (Overwrite::IfDifferent, Some(_)) => Action::Overwrite,
+        (Overwrite::IfDifferent, None) => Action::Compare,
+    }
+}
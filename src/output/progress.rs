@@ -0,0 +1,24 @@
+/// Receives progress of a batch of downloads
+///
+/// Implementations are called from several downloads at the same time when downloading in
+/// parallel.
+pub trait Progress: Send + Sync {
+    /// A batch of `episodes` episodes is starting
+    fn start_batch(&self, _episodes: usize) {}
+
+    /// An episode in the batch has been handled, whether it was downloaded or not
+    fn episode_done(&self) {}
+
+    /// All episodes in the batch have been handled
+    fn finish_batch(&self) {}
+
+    /// A download of `name` is starting with `downloaded` bytes already on disk. `total` is the
+    /// full size of the file if known.
+    fn start_download(&self, name: &str, downloaded: u64, total: Option<u64>) -> Box<dyn DownloadProgress>;
+}
+
+/// Receives progress of a single download. Dropped when the download ends.
+pub trait DownloadProgress: Send {
+    /// `bytes` more bytes have been written
+    fn advance(&mut self, bytes: u64);
+}
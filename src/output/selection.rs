@@ -0,0 +1,95 @@
+use crate::{Podcast, Episode};
+use super::WriteOptions;
+use std::{fmt, str::FromStr};
+
+/// Set of episode numbers like `1-10,42,100-`
+///
+/// Numbers are `<itunes:episode>`, the same as `{episode_number}` in templates. In feeds without
+/// episode numbers, they are indices counting from the oldest episode at 1, like `{episode_index}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpisodeRanges(Vec<(usize, Option<usize>)>);
+
+impl EpisodeRanges {
+
+    /// Checks if `number` is in one of the ranges
+    pub fn contains(&self, number: usize) -> bool {
+        self.0.iter()
+            .any(|&(start, end)| number >= start && end.is_none_or(|end| number <= end))
+    }
+
+}
+
+impl FromStr for EpisodeRanges {
+    type Err = String;
+
+    /// Parses comma separated indices and ranges. Ranges like `100-` have no end.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_index = |index: &str| match index.trim().parse() {
+            Ok(0) | Err(_) => Err(format!("Invalid episode index: {}", index.trim())),
+            Ok(index) => Ok(index),
+        };
+        let ranges = s.split(',')
+            .map(|range| {
+                let (start, end) = match range.split_once('-') {
+                    Some((start, end)) if end.trim().is_empty() => (parse_index(start)?, None),
+                    Some((start, end)) => (parse_index(start)?, Some(parse_index(end)?)),
+                    None => (parse_index(range)?, Some(parse_index(range)?)),
+                };
+                if end.is_some_and(|end| end < start) {
+                    return Err(format!("Invalid episode range: {}", range.trim()));
+                }
+                Ok((start, end))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self(ranges))
+    }
+}
+
+impl fmt::Display for EpisodeRanges {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ranges: Vec<String> = self.0.iter()
+            .map(|(start, end)| match end {
+                Some(end) if end == start => start.to_string(),
+                Some(end) => format!("{}-{}", start, end),
+                None => format!("{}-", start),
+            })
+            .collect();
+        f.write_str(&ranges.join(","))
+    }
+}
+
+/// Selects the episodes of `podcast` to download
+///
+/// If the input url pointed to a single episode, only that episode is selected. Otherwise episodes
+/// go through these steps in order:
+/// 1. Episodes not matching `options.filter` or `options.episodes` are left out. If any episode
+///    has an episode number, `options.episodes` matches episode numbers and episodes without one,
+///    like trailers, are left out. Otherwise it matches indices.
+/// 2. Episodes are sorted newest first, or oldest first with `options.oldest`
+/// 3. The first `options.offset` episodes are skipped
+/// 4. At most `options.limit` episodes are kept
+pub fn select_episodes<'a>(podcast: &'a Podcast, options: &WriteOptions) -> Vec<&'a Episode> {
+    if let Some(episode) = podcast.selected_episode.and_then(|i| podcast.episodes.get(i)) {
+        return vec![episode];
+    }
+    let numbered = podcast.episodes.iter().any(|episode| episode.episode_number.is_some());
+    let number = |episode: &Episode| match numbered {
+        true => episode.episode_number,
+        false => episode.index,
+    };
+    let mut episodes: Vec<&Episode> = podcast.episodes.iter()
+        .filter(|episode| options.filter.matches(episode))
+        .filter(|episode| match (&options.episodes, number(episode)) {
+            (Some(ranges), Some(number)) => ranges.contains(number),
+            (Some(_), None) => false,
+            (None, _) => true,
+        })
+        .collect();
+    if !options.oldest {
+        episodes.reverse();
+    }
+    episodes.into_iter()
+        .skip(options.offset.unwrap_or(0))
+        .take(options.limit.unwrap_or(usize::MAX))
+        .collect()
+}
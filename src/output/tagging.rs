@@ -0,0 +1,133 @@
+use crate::{Podcast, Episode, error::Error};
+use std::{io::Read, path::Path};
+use chrono::{Datelike, Timelike};
+use id3::TagLike;
+
+/// Cover image embedded in episodes
+pub struct Cover {
+    pub data: Vec<u8>,
+    pub png: bool,
+}
+
+impl Cover {
+    pub fn new(data: Vec<u8>) -> Self {
+        let png = data.starts_with(b"\x89PNG");
+        Self { data, png }
+    }
+}
+
+/// Writes metadata from `podcast` and `episode` into the audio file at `path`
+///
+/// Mp3 files get ID3v2 tags and mp4 files get iTunes style atoms. Other formats are left as is.
+pub fn embed_metadata(path: &Path, podcast: &Podcast, episode: &Episode, cover: Option<&Cover>) -> Result<(), Error> {
+    let mut header = [0; 12];
+    let read = std::fs::File::open(path)?.read(&mut header)?;
+    match &header[..read] {
+        [b'I', b'D', b'3', ..] => embed_id3(path, podcast, episode, cover),
+        [0xFF, second, ..] if second & 0xE0 == 0xE0 => embed_id3(path, podcast, episode, cover),
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => embed_mp4(path, podcast, episode, cover),
+        _ => {
+            log::debug!("Can not embed metadata in {}", path.display());
+            Ok(())
+        }
+    }
+}
+
+fn embed_id3(path: &Path, podcast: &Podcast, episode: &Episode, cover: Option<&Cover>) -> Result<(), Error> {
+    let mut tag = id3::Tag::read_from_path(path).unwrap_or_default();
+    tag.set_title(&episode.title);
+    tag.set_album(&podcast.title);
+    if let Some(author) = episode.author.as_ref().or(podcast.author.as_ref()) {
+        tag.set_artist(author);
+    }
+    if let Some(author) = &podcast.author {
+        tag.set_album_artist(author);
+    }
+    tag.set_genre("Podcast");
+    if let Some(date) = episode.pub_date {
+        tag.set_date_released(id3::Timestamp {
+            year: date.year(),
+            month: Some(date.month() as u8),
+            day: Some(date.day() as u8),
+            hour: Some(date.hour() as u8),
+            minute: Some(date.minute() as u8),
+            second: Some(date.second() as u8),
+        });
+    }
+    if let Some(number) = episode.episode_number {
+        tag.set_track(number as u32);
+    }
+    if let Some(season) = episode.season {
+        tag.set_disc(season as u32);
+    }
+    if let Some(description) = &episode.description {
+        tag.add_frame(id3::frame::Comment {
+            lang: "eng".to_string(),
+            description: String::new(),
+            text: description.clone(),
+        });
+        tag.set_text("TDES", description);
+    }
+    if let Some(guid) = &episode.guid {
+        tag.set_text("TGID", guid);
+    }
+    if let Some(feed_url) = &podcast.feed_url {
+        tag.add_frame(id3::Frame::link("WFED", feed_url));
+    }
+    // iTunes podcast flag
+    tag.add_frame(id3::Frame::with_content("PCST", id3::Content::Unknown(id3::frame::Unknown {
+        data: vec![0, 0, 0, 1],
+        version: id3::Version::Id3v24,
+    })));
+    if let Some(cover) = cover {
+        tag.add_frame(id3::frame::Picture {
+            mime_type: if cover.png { "image/png" } else { "image/jpeg" }.to_string(),
+            picture_type: id3::frame::PictureType::CoverFront,
+            description: String::new(),
+            data: cover.data.clone(),
+        });
+    }
+    tag.write_to_path(path, id3::Version::Id3v24)?;
+    Ok(())
+}
+
+fn embed_mp4(path: &Path, podcast: &Podcast, episode: &Episode, cover: Option<&Cover>) -> Result<(), Error> {
+    use mp4ameta::{Data, Img, ident};
+    let mut tag = mp4ameta::Tag::read_from_path(path)?;
+    tag.set_title(&episode.title);
+    tag.set_album(&podcast.title);
+    if let Some(author) = episode.author.as_ref().or(podcast.author.as_ref()) {
+        tag.set_artist(author);
+    }
+    if let Some(author) = &podcast.author {
+        tag.set_album_artist(author);
+    }
+    tag.set_genre("Podcast");
+    if let Some(date) = episode.pub_date {
+        tag.set_year(date.to_rfc3339());
+    }
+    if let Some(number) = episode.episode_number {
+        tag.set_track_number(number as u16);
+        tag.set_tv_episode(number as u32);
+    }
+    if let Some(season) = episode.season {
+        tag.set_tv_season(season as u32);
+    }
+    if let Some(description) = &episode.description {
+        tag.set_description(description);
+        tag.set_comment(description);
+    }
+    if let Some(guid) = &episode.guid {
+        tag.set_data(ident::PODCAST_EPISODE_GLOBAL_UNIQUE_ID, Data::Utf8(guid.clone()));
+    }
+    if let Some(feed_url) = &podcast.feed_url {
+        tag.set_data(ident::PODCAST_URL, Data::Utf8(feed_url.clone()));
+    }
+    tag.set_data(ident::PODCAST, Data::BeSigned(vec![1]));
+    if let Some(cover) = cover {
+        let data = cover.data.clone();
+        tag.set_artwork(if cover.png { Img::png(data) } else { Img::jpeg(data) });
+    }
+    tag.write_to_path(path)?;
+    Ok(())
+}
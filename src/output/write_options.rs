@@ -1,16 +1,78 @@
+use crate::Error;
+use super::{Collision, EpisodeFilter, EpisodeRanges, MediaServer, Overwrite, PlaylistFormat, Progress, Sanitize};
+use std::{path::PathBuf, sync::Arc};
+
 /// Options for downloading a podcast
+///
+/// See [`select_episodes`](super::select_episodes) for how `filter`, `episodes`, `oldest`, `offset`
+/// and `limit` combine.
 #[derive(Default)]
 pub struct WriteOptions {
     /// How many episodes should be downloaded
     pub limit: Option<usize>,
-    /// Offset in episodes to download
+    /// How many episodes to skip before downloading
     pub offset: Option<usize>,
     /// Start with the oldest episodes
     pub oldest: bool,
+    /// Conditions episodes have to meet, checked before `limit` and `offset`
+    pub filter: EpisodeFilter,
+    /// Episode numbers, or indices in feeds without them, to download. Checked before `limit` and `offset`.
+    pub episodes: Option<EpisodeRanges>,
     /// Output template
     pub template: String,
+    /// Directory the output template is relative to. Paths outside of it are rejected.
+    ///
+    /// Defaults to the directory in the template before the first variable.
+    pub output_dir: Option<PathBuf>,
+    /// Value of missing variables in the output template. Defaults to `UNKNOWN`.
+    pub template_fallback: Option<String>,
+    /// How strictly values in the output template are sanitized
+    pub sanitize: Sanitize,
+    /// What to do when the file of an episode exists
+    ///
+    /// `Overwrite::IfDifferent` can not be combined with `embed_metadata`, as embedded tags change
+    /// the size and content of the file.
+    pub overwrite: Overwrite,
+    /// What to do when episodes in a run have the same path
+    pub collision: Collision,
     /// Write episode description to seperate file
     pub write_episode_description: bool,
+    /// Write episode and podcast metadata to `<name>.info.json`
+    pub write_info_json: bool,
+    /// Write a playlist of the selected episodes in this format after downloading
+    pub write_playlist: Option<PlaylistFormat>,
+    /// Write podcast metadata, cover image and episode sidecars in the layout of this media server
+    pub media_server: Option<MediaServer>,
     /// Remove strings from the output file name
     pub remove_from_output: Vec<String>,
+    /// How many episodes to download at the same time. 0 is treated as 1
+    pub jobs: usize,
+    /// File recording downloaded episodes. Recorded episodes are skipped.
+    pub download_archive: Option<PathBuf>,
+    /// Write podcast and episode metadata into downloaded files. Always done for
+    /// `MediaServer::Audiobookshelf`, which reads episode details from tags.
+    pub embed_metadata: bool,
+    /// Skip checking that downloaded episodes are complete audio or video files
+    pub no_verify: bool,
+    /// Receives download progress
+    pub progress: Option<Arc<dyn Progress>>,
+}
+
+impl WriteOptions {
+    /// Checks that the options can be used together
+    pub fn validate(&self) -> Result<(), Error> {
+        // Tagged files never match the server, so every episode would be downloaded again
+        if self.overwrite == Overwrite::IfDifferent && self.embed_metadata {
+            return Err(Error::ConflictingOptions("overwrite if-different and embed metadata"));
+        }
+        if self.overwrite == Overwrite::IfDifferent && self.embeds_metadata() {
+            return Err(Error::ConflictingOptions("overwrite if-different and media server audiobookshelf, which embeds metadata"));
+        }
+        Ok(())
+    }
+
+    /// Returns true if metadata is written into downloaded files
+    pub fn embeds_metadata(&self) -> bool {
+        self.embed_metadata || self.media_server == Some(MediaServer::Audiobookshelf)
+    }
 }
@@ -0,0 +1,161 @@
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
+use podcast_dl::{DownloadProgress, Progress};
+use std::{
+    io::IsTerminal,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Time between progress messages when stderr is not a terminal
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Creates progress bars if stderr is a terminal and periodic log messages otherwise
+pub fn create_progress(multi: &MultiProgress) -> Arc<dyn Progress> {
+    if std::io::stderr().is_terminal() {
+        Arc::new(BarProgress {
+            multi: multi.clone(),
+            overall: Mutex::new(None),
+        })
+    } else {
+        Arc::new(LogProgress {
+            batch: Mutex::new(Batch { done: 0, total: 0, last_message: Instant::now() }),
+        })
+    }
+}
+
+/// Progress bar for each download and one for the whole batch
+struct BarProgress {
+    multi: MultiProgress,
+    overall: Mutex<Option<ProgressBar>>,
+}
+
+impl Progress for BarProgress {
+    fn start_batch(&self, episodes: usize) {
+        let bar = self.multi.add(ProgressBar::new(episodes as u64));
+        bar.set_style(
+            ProgressStyle::with_template("{prefix:>12.bold.blue} [{bar:30}] {pos}/{len} episodes ({elapsed})")
+                .unwrap()
+                .progress_chars("=> ")
+        );
+        bar.set_prefix("Total");
+        *self.overall.lock().unwrap() = Some(bar);
+    }
+
+    fn episode_done(&self) {
+        if let Some(bar) = &*self.overall.lock().unwrap() {
+            bar.inc(1);
+        }
+    }
+
+    fn finish_batch(&self) {
+        if let Some(bar) = self.overall.lock().unwrap().take() {
+            bar.finish_and_clear();
+        }
+    }
+
+    fn start_download(&self, name: &str, downloaded: u64, total: Option<u64>) -> Box<dyn DownloadProgress> {
+        let bar = ProgressBar::new(total.unwrap_or(0));
+        let style = match total {
+            Some(_) => ProgressStyle::with_template(
+                "{msg:40!} [{bar:30}] {bytes}/{total_bytes} {binary_bytes_per_sec} ETA {eta}"
+            ),
+            None => ProgressStyle::with_template("{msg:40!} {spinner} {bytes} {binary_bytes_per_sec}"),
+        };
+        bar.set_style(style.unwrap().progress_chars("=> "));
+        bar.set_message(name.to_string());
+        bar.set_position(downloaded);
+        let bar = match &*self.overall.lock().unwrap() {
+            Some(overall) => self.multi.insert_before(overall, bar),
+            None => self.multi.add(bar),
+        };
+        Box::new(BarDownload(bar))
+    }
+}
+
+struct BarDownload(ProgressBar);
+
+impl DownloadProgress for BarDownload {
+    fn advance(&mut self, bytes: u64) {
+        self.0.inc(bytes);
+    }
+}
+
+impl Drop for BarDownload {
+    fn drop(&mut self) {
+        self.0.finish_and_clear();
+    }
+}
+
+/// Logs progress at regular intervals
+struct LogProgress {
+    batch: Mutex<Batch>,
+}
+
+struct Batch {
+    done: usize,
+    total: usize,
+    last_message: Instant,
+}
+
+impl Progress for LogProgress {
+    fn start_batch(&self, episodes: usize) {
+        *self.batch.lock().unwrap() = Batch { done: 0, total: episodes, last_message: Instant::now() };
+    }
+
+    fn episode_done(&self) {
+        let mut batch = self.batch.lock().unwrap();
+        batch.done += 1;
+        if batch.last_message.elapsed() >= LOG_INTERVAL {
+            log::info!("Progress {}/{} episodes", batch.done, batch.total);
+            batch.last_message = Instant::now();
+        }
+    }
+
+    fn start_download(&self, name: &str, downloaded: u64, total: Option<u64>) -> Box<dyn DownloadProgress> {
+        let now = Instant::now();
+        Box::new(LogDownload {
+            name: name.to_string(),
+            downloaded,
+            total,
+            start: now,
+            start_position: downloaded,
+            last_message: now,
+        })
+    }
+}
+
+struct LogDownload {
+    name: String,
+    downloaded: u64,
+    total: Option<u64>,
+    start: Instant,
+    start_position: u64,
+    last_message: Instant,
+}
+
+impl DownloadProgress for LogDownload {
+    fn advance(&mut self, bytes: u64) {
+        self.downloaded += bytes;
+        if self.last_message.elapsed() < LOG_INTERVAL {
+            return;
+        }
+        self.last_message = Instant::now();
+        let speed = (self.downloaded - self.start_position) as f64 / self.start.elapsed().as_secs_f64();
+        match self.total {
+            Some(total) => log::info!(
+                "Progress {}: {}/{} ({}%) at {}/s",
+                self.name,
+                HumanBytes(self.downloaded),
+                HumanBytes(total),
+                self.downloaded * 100 / total.max(1),
+                HumanBytes(speed as u64),
+            ),
+            None => log::info!(
+                "Progress {}: {} at {}/s",
+                self.name,
+                HumanBytes(self.downloaded),
+                HumanBytes(speed as u64),
+            ),
+        }
+    }
+}
@@ -54,5 +54,5 @@ async fn itunes_search_format(
             })
         })
         .collect();
-    return Ok(results);
+    Ok(results)
 }
@@ -0,0 +1,107 @@
+//! Checks how episodes formatted to the same path are renamed
+
+mod common;
+
+use podcast_dl::{
+    Collision, Episode, Error, Podcast, WriteOptions, select_episodes,
+    output::{AssignedPath, assign_paths},
+};
+use std::path::PathBuf;
+
+/// Podcast with episodes indexed from 1 in the order of `titles`, oldest first
+fn podcast(titles: &[&str]) -> Podcast {
+    Podcast {
+        episodes: titles.iter().enumerate()
+            .map(|(i, title)| Episode {
+                index: Some(i + 1),
+                guid: Some(format!("guid-{}-abcdefgh", i + 1)),
+                pub_date: chrono::DateTime::parse_from_rfc3339(&format!("2023-01-0{}T00:00:00Z", i + 1)).ok(),
+                ..common::episode(title)
+            })
+            .collect(),
+        ..common::podcast()
+    }
+}
+
+fn options(template: &str, collision: Collision) -> WriteOptions {
+    WriteOptions { collision, ..common::options(template) }
+}
+
+/// Paths of the selected episodes, or `None` for collisions
+fn paths(podcast: &Podcast, options: &WriteOptions) -> Vec<Option<String>> {
+    let episodes = select_episodes(podcast, options);
+    assign_paths(podcast, &episodes, options).into_iter()
+        .map(|path| match path {
+            Ok(AssignedPath { path, .. }) => Some(path.to_string_lossy().into_owned()),
+            Err(Error::PathCollision(_)) => None,
+            Err(e) => panic!("Unexpected error {}", e),
+        })
+        .collect()
+}
+
+#[test]
+fn oldest_keeps_plain_path() {
+    let podcast = podcast(&["Title", "Title", "Other"]);
+    let options = options("out/{episode_title}.mp3", Collision::Index);
+    let episodes: Vec<&Episode> = podcast.episodes.iter().collect();
+    let assigned: Vec<_> = assign_paths(&podcast, &episodes, &options).into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(assigned, vec![
+        AssignedPath { path: PathBuf::from("out/Title.mp3"), collision: None },
+        AssignedPath { path: PathBuf::from("out/Title (2).mp3"), collision: Some(Collision::Index) },
+        AssignedPath { path: PathBuf::from("out/Other.mp3"), collision: None },
+    ]);
+}
+
+#[test]
+fn paths_do_not_depend_on_selection() {
+    let podcast = podcast(&["Title", "Title"]);
+    let newest = WriteOptions { limit: Some(1), ..options("out/{episode_title}.mp3", Collision::Index) };
+    assert_eq!(paths(&podcast, &newest), vec![Some("out/Title (2).mp3".to_string())]);
+    let all = options("out/{episode_title}.mp3", Collision::Index);
+    assert_eq!(paths(&podcast, &all), vec![Some("out/Title (2).mp3".to_string()), Some("out/Title.mp3".to_string())]);
+    // Skipped even though the episode with its path is not selected
+    let skip = WriteOptions { limit: Some(1), ..options("out/{episode_title}.mp3", Collision::Skip) };
+    assert_eq!(paths(&podcast, &skip), vec![None]);
+}
+
+#[test]
+fn suffixes() {
+    let mut podcast = podcast(&["Title", "Title", "Title"]);
+    podcast.episodes[2].guid = None;
+    let oldest = |collision| WriteOptions { oldest: true, ..options("out/{episode_title}.mp3", collision) };
+    assert_eq!(paths(&podcast, &oldest(Collision::Guid)), vec![
+        Some("out/Title.mp3".to_string()),
+        Some("out/Title [guid2abc].mp3".to_string()),
+        // Falls back to the index without a guid
+        Some("out/Title (3).mp3".to_string()),
+    ]);
+    assert_eq!(paths(&podcast, &oldest(Collision::Date)), vec![
+        Some("out/Title.mp3".to_string()),
+        Some("out/Title (2023-01-02).mp3".to_string()),
+        Some("out/Title (2023-01-03).mp3".to_string()),
+    ]);
+}
+
+#[test]
+fn suffix_position() {
+    let podcast = podcast(&["Ep. 1", "Ep. 1"]);
+    let oldest = |template| WriteOptions { oldest: true, ..options(template, Collision::Index) };
+    // The suffix goes before the last extension
+    assert_eq!(paths(&podcast, &oldest("out/{episode_title}.mp3"))[1].as_deref(), Some("out/Ep. 1 (2).mp3"));
+    assert_eq!(paths(&podcast, &oldest("out/{episode_title}.x"))[1].as_deref(), Some("out/Ep. 1 (2).x"));
+    // Without an extension, the suffix goes at the end
+    assert_eq!(paths(&podcast, &oldest("out/{podcast_title}"))[1].as_deref(), Some("out/Show (2)"));
+}
+
+#[test]
+fn renamed_path_taken() {
+    let podcast = podcast(&["Title", "Title (3)", "Title"]);
+    let options = WriteOptions { oldest: true, ..options("out/{episode_title}.mp3", Collision::Index) };
+    assert_eq!(paths(&podcast, &options), vec![
+        Some("out/Title.mp3".to_string()),
+        Some("out/Title (3).mp3".to_string()),
+        None,
+    ]);
+}
@@ -0,0 +1,36 @@
+//! Fixtures shared by the integration tests
+
+// Every test crate uses only some of these
+#![allow(dead_code)]
+
+use podcast_dl::{Episode, Podcast, WriteOptions};
+use std::path::PathBuf;
+
+/// Path in the temp directory unique to `name` and this test process
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("podcast-dl-{}-{}", name, std::process::id()))
+}
+
+/// Podcast titled `Show` without episodes
+pub fn podcast() -> Podcast {
+    Podcast {
+        title: "Show".to_string(),
+        ..Default::default()
+    }
+}
+
+/// Episode with only a title
+pub fn episode(title: &str) -> Episode {
+    Episode {
+        title: title.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Default options with the output template `template`
+pub fn options(template: &str) -> WriteOptions {
+    WriteOptions {
+        template: template.to_string(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,76 @@
+//! Checks how download responses are handled and downloaded files are recognized
+
+use podcast_dl::output::{ResponseAction, is_media, response_action};
+use reqwest::StatusCode;
+
+#[test]
+fn fresh_download() {
+    assert_eq!(response_action(StatusCode::OK, None, 0), ResponseAction::Replace);
+    assert_eq!(response_action(StatusCode::PARTIAL_CONTENT, Some("bytes 0-999/1000"), 0), ResponseAction::Replace);
+    // Part of the file without a range request
+    assert_eq!(response_action(StatusCode::PARTIAL_CONTENT, Some("bytes 500-999/1000"), 0), ResponseAction::Fail);
+    assert_eq!(response_action(StatusCode::PARTIAL_CONTENT, None, 0), ResponseAction::Fail);
+    // Checked as errors later
+    assert_eq!(response_action(StatusCode::NOT_FOUND, None, 0), ResponseAction::Replace);
+    assert_eq!(response_action(StatusCode::RANGE_NOT_SATISFIABLE, Some("bytes */1000"), 0), ResponseAction::Replace);
+}
+
+#[test]
+fn resumed_download() {
+    assert_eq!(response_action(StatusCode::PARTIAL_CONTENT, Some("bytes 500-999/1000"), 500), ResponseAction::Append);
+    assert_eq!(response_action(StatusCode::PARTIAL_CONTENT, Some("bytes 500-999/*"), 500), ResponseAction::Append);
+    // Servers ignoring the range send the whole file
+    assert_eq!(response_action(StatusCode::OK, None, 500), ResponseAction::Replace);
+}
+
+#[test]
+fn misaligned_partial_response() {
+    assert_eq!(response_action(StatusCode::PARTIAL_CONTENT, Some("bytes 0-999/1000"), 500), ResponseAction::Restart);
+    assert_eq!(response_action(StatusCode::PARTIAL_CONTENT, Some("bytes 400-999/1000"), 500), ResponseAction::Restart);
+    assert_eq!(response_action(StatusCode::PARTIAL_CONTENT, None, 500), ResponseAction::Restart);
+}
+
+#[test]
+fn range_not_satisfiable() {
+    // The part file is the whole file
+    assert_eq!(response_action(StatusCode::RANGE_NOT_SATISFIABLE, Some("bytes */1000"), 1000), ResponseAction::Complete);
+    // The part file is larger than the file on the server
+    assert_eq!(response_action(StatusCode::RANGE_NOT_SATISFIABLE, Some("bytes */800"), 1000), ResponseAction::Restart);
+    assert_eq!(response_action(StatusCode::RANGE_NOT_SATISFIABLE, None, 1000), ResponseAction::VerifyPart);
+    assert_eq!(response_action(StatusCode::RANGE_NOT_SATISFIABLE, Some("bytes */*"), 1000), ResponseAction::VerifyPart);
+}
+
+#[test]
+fn media_headers() {
+    let media: &[&[u8]] = &[
+        b"ID3\x04\x00\x00\x00\x00\x00\x00\x00\x00",
+        b"\xFF\xFB\x90\x64\x00\x00\x00\x00\x00\x00\x00\x00",
+        // Adts aac
+        b"\xFF\xF1\x50\x80\x00\x1F\xFC\x00\x00\x00\x00\x00",
+        b"\x00\x00\x00\x20ftypM4A \x00\x00",
+        b"\x00\x00\x00\x14ftypqt  \x00\x00",
+        b"\x00\x00\x6B\x6Cmoov\x00\x00\x00\x6C",
+        b"\x00\x00\x00\x08wide\x00\x00\x00\x00",
+        b"\x00\x01\x00\x00mdat\x00\x00\x00\x00",
+        b"\x00\x00\x00\x08free\x00\x00\x00\x00",
+        b"OggS\x00\x02\x00\x00\x00\x00\x00\x00",
+        b"fLaC\x00\x00\x00\x22\x00\x00\x00\x00",
+        b"RIFF\x24\x08\x00\x00WAVE",
+        b"\x1A\x45\xDF\xA3\x9F\x42\x86\x81\x01\x42\xF7\x81",
+    ];
+    for header in media {
+        assert!(is_media(header), "{:?}", header);
+    }
+    let not_media: &[&[u8]] = &[
+        b"",
+        b"<!DOCTYPE html>",
+        b"<?xml version=",
+        b"{\"error\": 1}",
+        b"RIFF\x24\x08\x00\x00AVI ",
+        b"\x00\x00\x00\x08skip",
+        b"ftyp",
+    ];
+    for header in not_media {
+        assert!(!is_media(header), "{:?}", header);
+    }
+}
@@ -0,0 +1,439 @@
+//! Resolves urls from supported sites against fixtures instead of the network
+
+use bytes::Bytes;
+use podcast_dl::{
+    Error, Extractor, ExtractorRegistry, Podcast,
+    error::ParseError,
+    feed::{Fetch, Strategy, execute_strategy},
+};
+use std::future::Future;
+use url::Url;
+
+const FEED: &str = "feed.xml";
+
+/// Maps urls to files in `tests/fixtures`
+struct Fixtures(Vec<(&'static str, &'static str)>);
+
+impl Fetch for Fixtures {
+    fn fetch(&self, url: &Url) -> impl Future<Output = Result<Bytes, Error>> + Send {
+        let name = self.0.iter()
+            .find(|(fixture_url, _)| *fixture_url == url.as_str())
+            .map(|(_, name)| *name)
+            .unwrap_or_else(|| panic!("Unexpected request to {}", url));
+        let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+        async move {
+            Ok(Bytes::from(std::fs::read(path)?))
+        }
+    }
+}
+
+async fn try_resolve(url: &str, fixtures: Vec<(&'static str, &'static str)>) -> Result<Podcast, Error> {
+    let strategy = ExtractorRegistry::default()
+        .create_strategy(url)
+        .unwrap();
+    execute_strategy(&strategy, &Fixtures(fixtures)).await
+}
+
+async fn resolve(url: &str, fixtures: Vec<(&'static str, &'static str)>) -> Podcast {
+    try_resolve(url, fixtures).await.unwrap()
+}
+
+async fn assert_resolves(url: &str, fixtures: Vec<(&'static str, &'static str)>) -> Podcast {
+    let podcast = resolve(url, fixtures).await;
+    assert_eq!(podcast.title, "Fixture Podcast");
+    assert_eq!(podcast.episodes.len(), 2);
+    podcast
+}
+
+/// Resolves the episode page at `url` of a host whose feed url comes from the page url and checks
+/// that the second episode is selected
+async fn assert_episode_page(url: &'static str, feed: &'static str) {
+    let podcast = assert_resolves(url, vec![(feed, FEED), (url, "hosted_episode.html")]).await;
+    assert_eq!(podcast.selected_episode, Some(1), "{}", url);
+}
+
+/// Resolves the show page at `url` and checks that no episode is selected
+async fn assert_show_page(url: &str, fixtures: Vec<(&'static str, &'static str)>) {
+    let podcast = assert_resolves(url, fixtures).await;
+    assert_eq!(podcast.selected_episode, None, "{}", url);
+}
+
+#[tokio::test]
+async fn podbean() {
+    let feed = "https://feed.podbean.com/fixturepodcast/feed.xml";
+    assert_show_page("https://fixturepodcast.podbean.com/", vec![(feed, FEED)]).await;
+    assert_episode_page("https://fixturepodcast.podbean.com/e/episode-2-second/", feed).await;
+    let podcast = assert_resolves(
+        "https://www.podbean.com/ew/pb-abcde-123456",
+        vec![
+            ("https://www.podbean.com/ew/pb-abcde-123456", "podbean_episode.html"),
+            (feed, FEED),
+        ],
+    ).await;
+    assert_eq!(podcast.selected_episode, Some(1));
+}
+
+#[tokio::test]
+async fn buzzsprout() {
+    let feed = "https://feeds.buzzsprout.com/123456.rss";
+    assert_show_page("https://www.buzzsprout.com/123456", vec![(feed, FEED)]).await;
+    assert_episode_page("https://www.buzzsprout.com/123456/7890123", feed).await;
+    assert_show_page(
+        "https://fixturepodcast.buzzsprout.com/",
+        vec![
+            ("https://fixturepodcast.buzzsprout.com/", "buzzsprout_site.html"),
+            (feed, FEED),
+        ],
+    ).await;
+}
+
+#[tokio::test]
+async fn libsyn() {
+    let feed = "https://fixturepodcast.libsyn.com/rss";
+    assert_show_page("https://fixturepodcast.libsyn.com/", vec![(feed, FEED)]).await;
+    assert_show_page("https://fixturepodcast.libsyn.com/page/2/size/25", vec![(feed, FEED)]).await;
+    assert_episode_page("https://fixturepodcast.libsyn.com/episode-2-second", feed).await;
+}
+
+#[tokio::test]
+async fn simplecast() {
+    let feed = "https://feeds.simplecast.com/AbC_12-x";
+    assert_show_page(
+        "https://fixture-podcast.simplecast.com/",
+        vec![("https://fixture-podcast.simplecast.com/", "simplecast_show.html"), (feed, FEED)],
+    ).await;
+    let podcast = assert_resolves(
+        "https://fixture-podcast.simplecast.com/episodes/episode-2-second",
+        vec![
+            ("https://fixture-podcast.simplecast.com/episodes/episode-2-second", "simplecast_episode.html"),
+            (feed, FEED),
+        ],
+    ).await;
+    assert_eq!(podcast.selected_episode, Some(1));
+}
+
+#[tokio::test]
+async fn acast() {
+    let feed = "https://feeds.acast.com/public/shows/fixture-podcast";
+    assert_show_page("https://shows.acast.com/fixture-podcast", vec![(feed, FEED)]).await;
+    assert_show_page("https://play.acast.com/s/fixture-podcast", vec![(feed, FEED)]).await;
+    assert_episode_page("https://shows.acast.com/fixture-podcast/episodes/episode-2", feed).await;
+    assert_episode_page("https://play.acast.com/s/fixture-podcast/episode-2", feed).await;
+    assert_episode_page("https://embed.acast.com/fixture-podcast/episode-2", feed).await;
+}
+
+#[tokio::test]
+async fn omny() {
+    let feed = "https://omny.fm/shows/fixture-podcast/playlists/podcast.rss";
+    assert_show_page("https://omny.fm/shows/fixture-podcast", vec![(feed, FEED)]).await;
+    assert_episode_page("https://omny.fm/shows/fixture-podcast/episode-2-second", feed).await;
+}
+
+#[tokio::test]
+async fn transistor() {
+    let feed = "https://feeds.transistor.fm/fixture-podcast";
+    assert_show_page("https://fixture-podcast.transistor.fm/", vec![(feed, FEED)]).await;
+    assert_episode_page("https://fixture-podcast.transistor.fm/episodes/episode-2-second", feed).await;
+    let podcast = assert_resolves(
+        "https://share.transistor.fm/s/1a2b3c4d",
+        vec![
+            ("https://share.transistor.fm/s/1a2b3c4d", "transistor_share.html"),
+            (feed, FEED),
+        ],
+    ).await;
+    assert_eq!(podcast.selected_episode, Some(1));
+}
+
+#[tokio::test]
+async fn captivate() {
+    let feed = "https://feeds.captivate.fm/fixture-podcast/";
+    assert_show_page("https://fixture-podcast.captivate.fm/", vec![(feed, FEED)]).await;
+    assert_episode_page("https://fixture-podcast.captivate.fm/episode/episode-2-second", feed).await;
+    let podcast = assert_resolves(
+        "https://player.captivate.fm/episode/0f1e2d3c-aaaa-bbbb-cccc-123456789abc",
+        vec![
+            ("https://player.captivate.fm/episode/0f1e2d3c-aaaa-bbbb-cccc-123456789abc", "captivate_player.html"),
+            (feed, FEED),
+        ],
+    ).await;
+    assert_eq!(podcast.selected_episode, Some(1));
+}
+
+#[tokio::test]
+async fn spreaker() {
+    let feed = "https://www.spreaker.com/show/4242/episodes/feed";
+    assert_show_page(
+        "https://www.spreaker.com/podcast/fixture-podcast--4242",
+        vec![(feed, FEED)],
+    ).await;
+    assert_show_page(
+        "https://www.spreaker.com/show/fixture-podcast",
+        vec![
+            ("https://api.spreaker.com/v2/shows/fixture-podcast", "spreaker_show.json"),
+            (feed, FEED),
+        ],
+    ).await;
+    let podcast = assert_resolves(
+        "https://www.spreaker.com/episode/episode-2-second--777",
+        vec![
+            ("https://api.spreaker.com/v2/episodes/777", "spreaker_episode.json"),
+            (feed, FEED),
+            ("https://www.spreaker.com/episode/episode-2-second--777", "hosted_episode.html"),
+        ],
+    ).await;
+    assert_eq!(podcast.selected_episode, Some(1));
+}
+
+const ITUNES_LOOKUP: (&str, &str) = ("https://itunes.apple.com/lookup?id=1000000000", "itunes_lookup.json");
+const EXAMPLE_FEED: (&str, &str) = ("https://example.com/feed.xml", FEED);
+
+#[tokio::test]
+async fn overcast() {
+    assert_resolves(
+        "https://overcast.fm/itunes1000000000/fixture-podcast",
+        vec![ITUNES_LOOKUP, EXAMPLE_FEED],
+    ).await;
+    assert_resolves(
+        "https://overcast.fm/+AbCdEfG",
+        vec![("https://overcast.fm/+AbCdEfG", "overcast_episode.html"), ITUNES_LOOKUP, EXAMPLE_FEED],
+    ).await;
+}
+
+#[tokio::test]
+async fn pocketcasts() {
+    assert_resolves(
+        "https://pca.st/abc123",
+        vec![("https://pca.st/abc123", "pocketcasts.html"), ITUNES_LOOKUP, EXAMPLE_FEED],
+    ).await;
+}
+
+#[tokio::test]
+async fn castro() {
+    assert_resolves(
+        "https://castro.fm/episode/AbC123",
+        vec![("https://castro.fm/episode/AbC123", "castro_episode.html"), ITUNES_LOOKUP, EXAMPLE_FEED],
+    ).await;
+}
+
+#[tokio::test]
+async fn castbox() {
+    assert_resolves(
+        "https://castbox.fm/channel/Fixture-Podcast-id424242",
+        vec![("https://castbox.fm/channel/Fixture-Podcast-id424242", "castbox_channel.html"), EXAMPLE_FEED],
+    ).await;
+}
+
+#[tokio::test]
+async fn podcast_addict() {
+    assert_resolves(
+        "https://podcastaddict.com/podcast/fixture-podcast/4242",
+        vec![("https://podcastaddict.com/podcast/fixture-podcast/4242", "podcastaddict.html"), EXAMPLE_FEED],
+    ).await;
+}
+
+#[tokio::test]
+async fn podcast_index() {
+    assert_resolves(
+        "https://podcastindex.org/podcast/4242",
+        vec![("https://podcastindex.org/api/podcasts/byfeedid?id=4242", "podcastindex.json"), EXAMPLE_FEED],
+    ).await;
+}
+
+#[tokio::test]
+async fn google_podcasts() {
+    assert_resolves(
+        "https://podcasts.google.com/feed/aHR0cHM6Ly9leGFtcGxlLmNvbS9mZWVkLnhtbA",
+        vec![EXAMPLE_FEED],
+    ).await;
+    assert_resolves(
+        "https://podcasts.google.com/feed/aHR0cHM6Ly9leGFtcGxlLmNvbS9mZWVkLnhtbA/episode/Zml4dHVyZS1lcGlzb2RlLTI",
+        vec![EXAMPLE_FEED],
+    ).await;
+    assert_resolves(
+        "https://www.google.com/podcasts?feed=aHR0cHM6Ly9leGFtcGxlLmNvbS9wb2RjYXN0L2ZlZWQueG1sP2Zvcm1hdD1yc3M%3D",
+        vec![("https://example.com/podcast/feed.xml?format=rss", FEED)],
+    ).await;
+}
+
+#[tokio::test]
+async fn itunes_episode() {
+    let podcast = resolve(
+        "https://podcasts.apple.com/us/podcast/episode-2-second/id1000000000?i=1000000002",
+        vec![
+            ITUNES_LOOKUP,
+            EXAMPLE_FEED,
+            ("https://itunes.apple.com/lookup?id=1000000000&entity=podcastEpisode&limit=200", "itunes_episodes.json"),
+        ],
+    ).await;
+    assert_eq!(podcast.selected_episode, Some(1));
+    let podcast = resolve(
+        "https://podcasts.apple.com/us/podcast/fixture-podcast/id1000000000",
+        vec![ITUNES_LOOKUP, EXAMPLE_FEED],
+    ).await;
+    assert_eq!(podcast.selected_episode, None);
+}
+
+#[tokio::test]
+async fn itunes_episode_missing_from_lookup() {
+    let fixtures = || vec![
+        ITUNES_LOOKUP,
+        EXAMPLE_FEED,
+        ("https://itunes.apple.com/lookup?id=1000000000&entity=podcastEpisode&limit=200", "itunes_episodes.json"),
+    ];
+    // Matched by the title in the url
+    let podcast = resolve(
+        "https://podcasts.apple.com/us/podcast/episode-1-first/id1000000000?i=1000000099",
+        fixtures(),
+    ).await;
+    assert_eq!(podcast.selected_episode, Some(0));
+    let result = try_resolve(
+        "https://podcasts.apple.com/us/podcast/episode-9-ninth/id1000000000?i=1000000099",
+        fixtures(),
+    ).await;
+    match result {
+        Err(Error::EpisodeNotFound(message)) => assert!(message.contains("newest 200 episodes"), "{}", message),
+        other => panic!("Expected EpisodeNotFound, got {:?}", other.map(|podcast| podcast.selected_episode)),
+    }
+}
+
+#[tokio::test]
+async fn rss_dot_com_episode() {
+    let podcast = resolve(
+        "https://rss.com/podcasts/fixture-podcast/123456/",
+        vec![
+            ("https://media.rss.com/fixture-podcast/feed.xml", FEED),
+            ("https://rss.com/podcasts/fixture-podcast/123456/", "rsscom_episode.html"),
+        ],
+    ).await;
+    assert_eq!(podcast.selected_episode, Some(0));
+}
+
+#[tokio::test]
+async fn generic_episode() {
+    let podcast = resolve(
+        "https://example.com/episodes/2",
+        vec![("https://example.com/episodes/2", "generic_episode.html"), EXAMPLE_FEED],
+    ).await;
+    assert_eq!(podcast.selected_episode, Some(1));
+}
+
+#[tokio::test]
+async fn generic_show() {
+    // The player for the latest episode does not select it
+    for url in ["https://example.com/", "https://example.com/podcast"] {
+        let podcast = resolve(url, vec![(url, "generic_show.html"), EXAMPLE_FEED]).await;
+        assert_eq!(podcast.selected_episode, None, "{}", url);
+    }
+    // The channel link is the show page even if it has episode meta tags
+    let podcast = resolve(
+        "https://example.com",
+        vec![("https://example.com/", "generic_episode.html"), EXAMPLE_FEED],
+    ).await;
+    assert_eq!(podcast.selected_episode, None);
+}
+
+/// Extractor for podbean pages reading the feed url from the page, registered in tests
+struct CustomPodbean {
+    pattern: &'static str,
+}
+
+impl Extractor for CustomPodbean {
+    fn name(&self) -> &str {
+        "custom podbean"
+    }
+
+    fn patterns(&self) -> &[&str] {
+        &["https://<name>.podbean.com/"]
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        url.host_str().is_some_and(|host| host.ends_with(".podbean.com"))
+    }
+
+    fn build(&self, url: &Url) -> Result<Strategy, ParseError> {
+        Ok(Strategy::from_url(url.clone()).find(self.pattern).rss())
+    }
+}
+
+/// Extractor whose strategy never parses a podcast
+struct Incomplete;
+
+impl Extractor for Incomplete {
+    fn name(&self) -> &str {
+        "incomplete"
+    }
+
+    fn patterns(&self) -> &[&str] {
+        &["https://incomplete.example.com/"]
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        url.host_str() == Some("incomplete.example.com")
+    }
+
+    fn build(&self, url: &Url) -> Result<Strategy, ParseError> {
+        Ok(Strategy::from_url(url.clone()).find("https://[^\"]+"))
+    }
+}
+
+async fn resolve_with(registry: &ExtractorRegistry, url: &str, fixtures: Vec<(&'static str, &'static str)>) -> Result<Podcast, Error> {
+    let strategy = registry.create_strategy(url).unwrap();
+    execute_strategy(&strategy, &Fixtures(fixtures)).await
+}
+
+#[tokio::test]
+async fn registered_extractor() {
+    let mut registry = ExtractorRegistry::default();
+    registry.register(CustomPodbean { pattern: r"https://feed\.podbean\.com/[\w.-]+/feed\.xml" });
+    let url = Url::parse("https://fixturepodcast.podbean.com/").unwrap();
+    let extractor = registry.extractors()
+        .find(|extractor| extractor.matches(&url))
+        .unwrap();
+    assert_eq!(extractor.name(), "custom podbean");
+    // The built-in extractor would derive the feed url without requesting the page
+    let podcast = resolve_with(
+        &registry,
+        "https://fixturepodcast.podbean.com/",
+        vec![
+            ("https://fixturepodcast.podbean.com/", "podbean_episode.html"),
+            ("https://feed.podbean.com/fixturepodcast/feed.xml", FEED),
+        ],
+    ).await.unwrap();
+    assert_eq!(podcast.title, "Fixture Podcast");
+}
+
+#[tokio::test]
+async fn invalid_custom_strategies() {
+    let mut registry = ExtractorRegistry::default();
+    registry.register(CustomPodbean { pattern: "(" });
+    registry.register(Incomplete);
+    let result = resolve_with(
+        &registry,
+        "https://fixturepodcast.podbean.com/",
+        vec![("https://fixturepodcast.podbean.com/", "podbean_episode.html")],
+    ).await;
+    assert!(matches!(result, Err(Error::Parse(ParseError::Regex(_)))));
+    let result = resolve_with(
+        &registry,
+        "https://incomplete.example.com/",
+        vec![("https://incomplete.example.com/", "generic_episode.html")],
+    ).await;
+    assert!(matches!(result, Err(Error::IncompleteStrategy(_))));
+}
+
+#[test]
+fn feed_hosts_use_generic_extractor() {
+    let registry = ExtractorRegistry::default();
+    for url in [
+        "https://feed.podbean.com/fixturepodcast/feed.xml",
+        "https://feeds.buzzsprout.com/123456.rss",
+        "https://feeds.simplecast.com/AbC_12-x",
+        "https://feeds.transistor.fm/fixture-podcast",
+    ] {
+        let url = Url::parse(url).unwrap();
+        let extractor = registry.extractors()
+            .find(|extractor| extractor.matches(&url))
+            .unwrap();
+        assert_eq!(extractor.name(), "generic", "{}", url);
+    }
+}
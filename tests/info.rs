@@ -0,0 +1,33 @@
+//! Checks that info files can be read back
+
+mod common;
+
+use podcast_dl::{Episode, EpisodeInfo, EpisodeType, Podcast};
+
+#[test]
+fn info_json_round_trip() {
+    let episode = Episode {
+        title: "Episode".to_string(),
+        link: "https://example.com/episode.mp3".to_string(),
+        guid: Some("episode-guid".to_string()),
+        pub_date: chrono::DateTime::parse_from_rfc3339("2023-01-02T08:00:00+01:00").ok(),
+        episode_type: Some(EpisodeType::Bonus),
+        ..Default::default()
+    };
+    let podcast = Podcast {
+        title: "Podcast".to_string(),
+        episodes: vec![episode.clone()],
+        feed_url: Some("https://example.com/feed.xml".to_string()),
+        ..Default::default()
+    };
+    let path = common::temp_path("info").with_extension("info.json");
+    EpisodeInfo::new(&podcast, &episode).write(&path).unwrap();
+    let info = EpisodeInfo::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(info.episode.title, "Episode");
+    assert_eq!(info.episode.guid.as_deref(), Some("episode-guid"));
+    assert_eq!(info.episode.pub_date, episode.pub_date);
+    assert_eq!(info.episode.episode_type, Some(EpisodeType::Bonus));
+    assert_eq!(info.podcast.feed_url.as_deref(), Some("https://example.com/feed.xml"));
+    assert!(info.podcast.episodes.is_empty());
+}
@@ -0,0 +1,81 @@
+//! Checks feeds generated for downloaded episodes
+
+mod common;
+
+use podcast_dl::{Episode, EpisodeInfo, LocalFeedOptions, Podcast, generate_feed, output::info_path};
+
+#[test]
+fn feed_from_downloads() {
+    let directory = common::temp_path("feed");
+    let show = directory.join("My Show");
+    std::fs::create_dir_all(&show).unwrap();
+    let podcast = Podcast {
+        title: "My Show".to_string(),
+        description: Some("About the show".to_string()),
+        ..Default::default()
+    };
+    let episode = Episode {
+        title: "Older".to_string(),
+        guid: Some("older-guid".to_string()),
+        pub_date: chrono::DateTime::parse_from_rfc3339("2020-01-01T00:00:00Z").ok(),
+        duration: Some(3725),
+        ..Default::default()
+    };
+    std::fs::write(show.join("Older.mp3"), b"older audio").unwrap();
+    EpisodeInfo::new(&podcast, &episode).write(&info_path(&show.join("Older.mp3"))).unwrap();
+    // Without info, the file name is the title and the modification time is the date
+    std::fs::write(show.join("New one.m4a"), b"new").unwrap();
+    std::fs::write(show.join("notes.txt"), b"not an episode").unwrap();
+    std::fs::write(show.join("cover.jpg"), b"image").unwrap();
+
+    let options = LocalFeedOptions {
+        directory: directory.clone(),
+        base_url: "https://example.com/podcasts".parse().unwrap(),
+        download_archive: None,
+    };
+    let feed = generate_feed(&options);
+    std::fs::remove_dir_all(&directory).unwrap();
+    let channel: rss::Channel = feed.unwrap().parse().unwrap();
+
+    assert_eq!(channel.title, "My Show");
+    assert_eq!(channel.description, "About the show");
+    assert_eq!(channel.image.unwrap().url, "https://example.com/podcasts/My%20Show/cover.jpg");
+    let items = channel.items;
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].title.as_deref(), Some("New one"));
+    let enclosure = items[0].enclosure.as_ref().unwrap();
+    assert_eq!(enclosure.url, "https://example.com/podcasts/My%20Show/New%20one.m4a");
+    assert_eq!(enclosure.length, "3");
+    assert_eq!(enclosure.mime_type, "audio/mp4");
+    assert_eq!(items[1].title.as_deref(), Some("Older"));
+    assert_eq!(items[1].guid.as_ref().unwrap().value, "older-guid");
+    assert_eq!(items[1].pub_date.as_deref(), Some("Wed, 01 Jan 2020 00:00:00 +0000"));
+    assert_eq!(items[1].itunes_ext.as_ref().unwrap().duration.as_deref(), Some("01:02:05"));
+}
+
+#[test]
+fn feed_from_media_server_layout() {
+    let directory = common::temp_path("feed-kodi");
+    let show = directory.join("My Show");
+    std::fs::create_dir_all(&show).unwrap();
+    // Files written with `--media-server kodi`
+    std::fs::write(show.join("tvshow.nfo"), b"<tvshow/>").unwrap();
+    std::fs::write(show.join("poster.jpg"), b"image").unwrap();
+    std::fs::write(show.join("Episode.mp3"), b"audio").unwrap();
+    std::fs::write(show.join("Episode.nfo"), b"<episodedetails/>").unwrap();
+    std::fs::write(show.join("Episode-thumb.jpg"), b"image").unwrap();
+
+    let options = LocalFeedOptions {
+        directory: directory.clone(),
+        base_url: "https://example.com/podcasts/".parse().unwrap(),
+        download_archive: None,
+    };
+    let feed = generate_feed(&options);
+    std::fs::remove_dir_all(&directory).unwrap();
+    let channel: rss::Channel = feed.unwrap().parse().unwrap();
+
+    assert_eq!(channel.image.unwrap().url, "https://example.com/podcasts/My%20Show/poster.jpg");
+    assert_eq!(channel.items.len(), 1);
+    let image = channel.items[0].itunes_ext.as_ref().unwrap().image.as_deref();
+    assert_eq!(image, Some("https://example.com/podcasts/My%20Show/Episode-thumb.jpg"));
+}
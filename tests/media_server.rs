@@ -0,0 +1,62 @@
+//! Checks media server files written for episodes that are already downloaded
+
+mod common;
+
+use podcast_dl::{Episode, MediaServer, Podcast, WriteOptions, download_podcast};
+
+#[tokio::test]
+async fn sidecars_of_existing_episodes() {
+    let directory = common::temp_path("media-server");
+    let show = directory.join("Show");
+    std::fs::create_dir_all(&show).unwrap();
+    std::fs::write(show.join("Existing.mp3"), b"audio").unwrap();
+    std::fs::write(show.join("poster.jpg"), b"old cover").unwrap();
+    let podcast = Podcast {
+        // Unreachable, so the existing cover has to be kept to pass
+        image: Some("http://localhost:1/cover.jpg".to_string()),
+        episodes: vec![Episode {
+            link: "http://localhost:1/existing.mp3".to_string(),
+            ..common::episode("Existing")
+        }],
+        ..common::podcast()
+    };
+    let options = WriteOptions {
+        media_server: Some(MediaServer::Kodi),
+        ..common::options(&format!("{}/{{podcast_title}}/{{episode_title}}.mp3", directory.display()))
+    };
+    let result = download_podcast(&podcast, &options).await;
+    let nfo = std::fs::read_to_string(show.join("Existing.nfo"));
+    let tvshow = std::fs::read_to_string(show.join("tvshow.nfo"));
+    let cover = std::fs::read(show.join("poster.jpg"));
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    result.unwrap();
+    assert!(nfo.unwrap().contains("<title>Existing</title>"));
+    assert!(tvshow.unwrap().contains("<title>Show</title>"));
+    assert_eq!(cover.unwrap(), b"old cover");
+}
+
+#[tokio::test]
+async fn podcast_directory_failure_keeps_downloads() {
+    let directory = common::temp_path("media-server-dir");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("Existing"), b"audio").unwrap();
+    let podcast = Podcast {
+        episodes: vec![
+            // The podcast files go next to the first episode, which has no file name
+            Episode { link: "http://localhost:1/untitled.mp3".to_string(), ..common::episode("") },
+            Episode { link: "http://localhost:1/existing.mp3".to_string(), ..common::episode("Existing") },
+        ],
+        ..common::podcast()
+    };
+    let options = WriteOptions {
+        media_server: Some(MediaServer::Jellyfin),
+        ..common::options(&format!("{}/{{episode_title}}", directory.display()))
+    };
+    let result = download_podcast(&podcast, &options).await;
+    let tvshow = directory.join("tvshow.nfo").exists();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    result.unwrap();
+    assert!(!tvshow);
+}
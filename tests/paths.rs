@@ -0,0 +1,65 @@
+//! Checks that formatted episode paths stay inside the output directory
+
+mod common;
+
+use common::options;
+use podcast_dl::{Error, WriteOptions, output::episode_path};
+use std::path::{Path, PathBuf};
+
+fn path(title: &str, options: &WriteOptions) -> Result<PathBuf, Error> {
+    episode_path(&common::podcast(), &common::episode(title), options)
+}
+
+#[test]
+fn separators_in_values() {
+    let path = path("../../.bashrc", &options("out/{episode_title}")).unwrap();
+    assert_eq!(path.parent(), Some(Path::new("out")));
+}
+
+#[test]
+fn traversal_in_template() {
+    let result = path("bashrc", &options("out/{podcast_title}/../../../.{episode_title}"));
+    assert!(matches!(result, Err(Error::PathTraversal(_))));
+}
+
+#[test]
+fn nul_byte() {
+    // Values are sanitized, so only the template can add one
+    let sanitized = path("a\0b", &options("out/{episode_title}.mp3")).unwrap();
+    assert_eq!(sanitized.to_str().map(|path| path.contains('\0')), Some(false));
+    let result = path("a", &options("out/\0{episode_title}.mp3"));
+    assert!(matches!(result, Err(Error::PathTraversal(_))));
+}
+
+#[test]
+fn removed_strings_forming_parent_dir() {
+    let options = WriteOptions {
+        remove_from_output: vec!["X".to_string()],
+        ..options("out/{episode_title}/episode.mp3")
+    };
+    let result = path("..X", &options);
+    assert!(matches!(result, Err(Error::PathTraversal(_))));
+}
+
+#[test]
+fn absolute_template_with_output_dir() {
+    let options = WriteOptions {
+        output_dir: Some(PathBuf::from("downloads")),
+        ..options("/etc/{episode_title}")
+    };
+    assert!(matches!(path("passwd", &options), Err(Error::PathTraversal(_))));
+    let options = WriteOptions { template: "{podcast_title}/{episode_title}.mp3".to_string(), ..options };
+    assert_eq!(path("Title", &options).unwrap(), Path::new("downloads/Show/Title.mp3"));
+}
+
+#[test]
+fn template_root() {
+    let options = options("/srv/podcasts/{podcast_title}/{episode_title}.mp3");
+    assert_eq!(path("Title", &options).unwrap(), Path::new("/srv/podcasts/Show/Title.mp3"));
+}
+
+#[test]
+fn empty_file_name() {
+    let result = path("", &options("out/{episode_title}"));
+    assert!(matches!(result, Err(Error::EmptyFileName(path)) if path == "out/"));
+}
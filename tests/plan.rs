@@ -0,0 +1,57 @@
+//! Checks that dry runs decide like downloads
+
+mod common;
+
+use podcast_dl::{Action, Episode, Overwrite, Podcast, SkipReason, WriteOptions, plan_podcast};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+};
+
+/// Serves a single request with an empty response of `content_length` bytes and returns its url
+fn serve_once(content_length: u64) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/episode.mp3", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 2 {
+            line.clear();
+        }
+        let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", content_length);
+        reader.get_mut().write_all(response.as_bytes()).unwrap();
+    });
+    url
+}
+
+#[tokio::test]
+async fn if_different_uses_server_size() {
+    let directory = common::temp_path("plan");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("Episode.mp3"), b"12345").unwrap();
+    let podcast = |content_length| Podcast {
+        episodes: vec![Episode {
+            link: serve_once(content_length),
+            // Same size as the file, but outdated
+            length: Some(5),
+            ..common::episode("Episode")
+        }],
+        ..common::podcast()
+    };
+    let options = WriteOptions {
+        overwrite: Overwrite::IfDifferent,
+        ..common::options(&format!("{}/{{episode_title}}.mp3", directory.display()))
+    };
+    let changed = podcast(10);
+    let changed = plan_podcast(&changed, &options, false).await;
+    let unchanged = podcast(5);
+    let unchanged = plan_podcast(&unchanged, &options, false).await;
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    let changed = changed.unwrap();
+    assert_eq!(changed[0].action, Action::Overwrite);
+    // Sizes are only reported when requested
+    assert_eq!(changed[0].size, None);
+    assert_eq!(unchanged.unwrap()[0].action, Action::Skip(SkipReason::Unchanged));
+}
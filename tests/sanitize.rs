@@ -0,0 +1,55 @@
+//! Checks how values in output templates are made safe for file names
+
+mod common;
+
+use podcast_dl::{Podcast, Sanitize, WriteOptions, output::episode_path};
+
+fn file_name(title: &str, template: &str, sanitize: Sanitize) -> String {
+    let options = WriteOptions { sanitize, ..common::options(template) };
+    let path = episode_path(&common::podcast(), &common::episode(title), &options).unwrap();
+    path.file_name().unwrap().to_str().unwrap().to_string()
+}
+
+#[test]
+fn truncate_on_char_boundary() {
+    // 400 bytes of two byte characters
+    let title = "é".repeat(200);
+    let name = file_name(&title, "out/{episode_title}.mp3", Sanitize::Posix);
+    assert!(name.len() <= 255);
+    assert!(name.ends_with(".mp3"));
+    let stem = name.strip_suffix(".mp3").unwrap();
+    assert!(stem.chars().all(|c| c == 'é'));
+    assert_eq!(stem.len(), 250);
+}
+
+#[test]
+fn truncate_keeps_directories() {
+    let podcast = Podcast { title: "b".repeat(300), ..common::podcast() };
+    let episode = common::episode(&"a".repeat(300));
+    let options = common::options("out/{podcast_title}/{episode_title}.m4a");
+    let path = episode_path(&podcast, &episode, &options).unwrap();
+    let parts: Vec<_> = path.iter().map(|part| part.to_str().unwrap().to_string()).collect();
+    assert_eq!(parts[0], "out");
+    assert_eq!(parts[1], "b".repeat(255));
+    assert_eq!(parts[2], format!("{}.m4a", "a".repeat(251)));
+}
+
+#[test]
+fn windows_reserved_names() {
+    assert_eq!(file_name("CON", "out/{episode_title}", Sanitize::Windows), "_CON");
+    assert_eq!(file_name("com1", "out/{episode_title}.mp3", Sanitize::Windows), "_com1.mp3");
+    assert_eq!(file_name("nul.txt", "out/{episode_title}", Sanitize::Windows), "_nul.txt");
+    // Only whole names are reserved
+    assert_eq!(file_name("CONSOLE", "out/{episode_title}", Sanitize::Windows), "CONSOLE");
+    // Posix has no reserved names
+    assert_eq!(file_name("CON", "out/{episode_title}", Sanitize::Posix), "CON");
+}
+
+#[test]
+fn reserved_characters() {
+    assert_eq!(file_name("a/b:c?", "out/{episode_title}", Sanitize::Posix), "a_b:c?");
+    assert_eq!(file_name("a/b:c?", "out/{episode_title}", Sanitize::Windows), "a_b_c_");
+    assert_eq!(file_name("Trailing. ", "out/{episode_title}", Sanitize::Windows), "Trailing");
+    assert_eq!(file_name("Café", "out/{episode_title}", Sanitize::Ascii), "Caf_");
+    assert_eq!(file_name("..", "out/{episode_title}", Sanitize::Posix), "__");
+}
@@ -0,0 +1,127 @@
+//! Checks how filters, episode ranges, ordering, offset and limit combine
+
+use podcast_dl::{Episode, EpisodeFilter, EpisodeRanges, Podcast, WriteOptions, select_episodes};
+
+/// Podcast with episodes indexed 1 to `count`, titled by their index
+fn podcast(count: usize) -> Podcast {
+    Podcast {
+        title: "Selection".to_string(),
+        episodes: (1..=count)
+            .map(|index| Episode {
+                title: index.to_string(),
+                index: Some(index),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn selected(podcast: &Podcast, options: &WriteOptions) -> Vec<usize> {
+    select_episodes(podcast, options).iter()
+        .map(|episode| episode.index.unwrap())
+        .collect()
+}
+
+fn ranges(ranges: &str) -> Option<EpisodeRanges> {
+    Some(ranges.parse().unwrap())
+}
+
+#[test]
+fn newest_first_by_default() {
+    let options = WriteOptions::default();
+    assert_eq!(selected(&podcast(3), &options), vec![3, 2, 1]);
+}
+
+#[test]
+fn oldest_first() {
+    let options = WriteOptions { oldest: true, ..Default::default() };
+    assert_eq!(selected(&podcast(3), &options), vec![1, 2, 3]);
+}
+
+#[test]
+fn offset_and_limit() {
+    let options = WriteOptions { offset: Some(2), limit: Some(3), ..Default::default() };
+    assert_eq!(selected(&podcast(10), &options), vec![8, 7, 6]);
+    let options = WriteOptions { offset: Some(2), limit: Some(3), oldest: true, ..Default::default() };
+    assert_eq!(selected(&podcast(10), &options), vec![3, 4, 5]);
+}
+
+#[test]
+fn limit_larger_than_offset() {
+    let options = WriteOptions { offset: Some(1), limit: Some(1), ..Default::default() };
+    assert_eq!(selected(&podcast(5), &options), vec![4]);
+}
+
+#[test]
+fn offset_past_end() {
+    let options = WriteOptions { offset: Some(5), ..Default::default() };
+    assert!(selected(&podcast(3), &options).is_empty());
+    let options = WriteOptions { offset: Some(2), limit: Some(5), ..Default::default() };
+    assert_eq!(selected(&podcast(3), &options), vec![1]);
+}
+
+#[test]
+fn index_ranges() {
+    let options = WriteOptions { episodes: ranges("1-3,5,8-"), oldest: true, ..Default::default() };
+    assert_eq!(selected(&podcast(10), &options), vec![1, 2, 3, 5, 8, 9, 10]);
+}
+
+#[test]
+fn index_ranges_before_offset_and_limit() {
+    let options = WriteOptions {
+        episodes: ranges("2-8"),
+        offset: Some(1),
+        limit: Some(2),
+        ..Default::default()
+    };
+    assert_eq!(selected(&podcast(10), &options), vec![7, 6]);
+}
+
+#[test]
+fn episode_number_ranges() {
+    // A trailer without a number comes first, so numbers are one less than indices
+    let mut podcast = podcast(6);
+    for episode in podcast.episodes.iter_mut().skip(1) {
+        episode.episode_number = episode.index.map(|index| index - 1);
+    }
+    let options = WriteOptions { episodes: ranges("1-2,5-"), oldest: true, ..Default::default() };
+    assert_eq!(selected(&podcast, &options), vec![2, 3, 6]);
+}
+
+#[test]
+fn filter_before_limit() {
+    let options = WriteOptions {
+        filter: EpisodeFilter {
+            reject_title: Some(regex::Regex::new("^[2-4]$").unwrap()),
+            ..Default::default()
+        },
+        limit: Some(2),
+        oldest: true,
+        ..Default::default()
+    };
+    assert_eq!(selected(&podcast(6), &options), vec![1, 5]);
+}
+
+#[test]
+fn selected_episode_ignores_options() {
+    let mut podcast = podcast(5);
+    podcast.selected_episode = Some(1);
+    let options = WriteOptions { episodes: ranges("4"), limit: Some(0), ..Default::default() };
+    assert_eq!(selected(&podcast, &options), vec![2]);
+}
+
+#[test]
+fn parse_ranges() {
+    let parsed: EpisodeRanges = " 1-10, 42,100-".parse().unwrap();
+    assert_eq!(parsed.to_string(), "1-10,42,100-");
+    assert!(parsed.contains(1) && parsed.contains(10) && parsed.contains(42) && parsed.contains(1000));
+    assert!(!parsed.contains(11) && !parsed.contains(99));
+}
+
+#[test]
+fn invalid_ranges() {
+    for invalid in ["", "0", "a", "5-3", "1,,2", "-4", "1-2-3"] {
+        assert!(invalid.parse::<EpisodeRanges>().is_err(), "{} should not parse", invalid);
+    }
+}
@@ -0,0 +1,74 @@
+//! Checks how output templates are parsed and rendered
+
+mod common;
+
+use podcast_dl::{Episode, output::episode_path};
+
+fn episode() -> Episode {
+    Episode {
+        season: Some(2),
+        ..common::episode("Ep. 12: Title")
+    }
+}
+
+/// Formats the path of `episode` with `template`
+fn format(template: &str, episode: &Episode) -> Result<String, podcast_dl::Error> {
+    episode_path(&common::podcast(), episode, &common::options(template))
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+#[test]
+fn optional_sections() {
+    let with_season = format("[S{season:02} ]{episode_title}.mp3", &episode()).unwrap();
+    assert_eq!(with_season, "S02 Ep. 12: Title.mp3");
+    let without_season = Episode { season: None, ..episode() };
+    assert_eq!(format("[S{season:02} ]{episode_title}.mp3", &without_season).unwrap(), "Ep. 12: Title.mp3");
+}
+
+#[test]
+fn brackets_without_variables() {
+    // Templates written before optional sections keep their brackets
+    assert_eq!(format("[Archive] {episode_title}.mp3", &episode()).unwrap(), "[Archive] Ep. 12: Title.mp3");
+    assert_eq!(format("[a [b] {season}]-{episode_title}.mp3", &episode()).unwrap(), "a [b] 2-Ep. 12: Title.mp3");
+    assert_eq!(format("x] [y {episode_title}.mp3", &episode()).unwrap(), "x] [y Ep. 12: Title.mp3");
+    assert_eq!(format("[[{season}]] {{{episode_title}}}.mp3", &episode()).unwrap(), "[2] {Ep. 12: Title}.mp3");
+}
+
+#[test]
+fn filters() {
+    let template = r#"{episode_title|replace("^Ep\\. \\d+: ","")|truncate(80)}.mp3"#;
+    assert_eq!(format(template, &episode()).unwrap(), "Title.mp3");
+    let long = Episode { title: format!("Ep. 1: {}", "a".repeat(100)), ..episode() };
+    assert_eq!(format(template, &long).unwrap(), format!("{}.mp3", "a".repeat(80)));
+    assert_eq!(format("{episode_title|slug}.mp3", &episode()).unwrap(), "ep-12-title.mp3");
+    assert_eq!(format("{episode_title|lower|replace(\"ep\",\"E\")}.mp3", &episode()).unwrap(), "E. 12: title.mp3");
+}
+
+#[test]
+fn default_filter() {
+    assert_eq!(format("{episode_number|default(\"none\")}.mp3", &episode()).unwrap(), "none.mp3");
+    // A missing value without a default uses the fallback
+    assert_eq!(format("{episode_number}.mp3", &episode()).unwrap(), "UNKNOWN.mp3");
+    let numbered = Episode { episode_number: Some(7), ..episode() };
+    assert_eq!(format("{episode_number|default(\"none\")}.mp3", &numbered).unwrap(), "7.mp3");
+}
+
+#[test]
+fn pad_filter() {
+    assert_eq!(format("{season|pad(3)}.mp3", &episode()).unwrap(), "002.mp3");
+    let numbered = Episode { season: Some(1234), ..episode() };
+    assert_eq!(format("{season|pad(3)}.mp3", &numbered).unwrap(), "1234.mp3");
+    // Values that are not numbers are kept
+    assert_eq!(format("{episode_title|pad(3)}.mp3", &episode()).unwrap(), "Ep. 12: Title.mp3");
+}
+
+#[test]
+fn invalid_templates() {
+    let invalid = |template: &str| matches!(format(template, &episode()), Err(podcast_dl::Error::InvalidTemplate(_)));
+    assert!(invalid("{episode_title.mp3"));
+    assert!(invalid("episode_title}.mp3"));
+    assert!(invalid("{episode_title|reverse}.mp3"));
+    assert!(invalid("{episode_title|truncate}.mp3"));
+    assert!(invalid("{episode_title|truncate(many)}.mp3"));
+    assert!(invalid("{episode_title|replace(\"(\",\"\")}.mp3"));
+}
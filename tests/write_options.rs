@@ -0,0 +1,42 @@
+//! Checks options that can not be used together
+
+mod common;
+
+use podcast_dl::{Episode, Error, MediaServer, Overwrite, Podcast, WriteOptions, download_podcast, plan_podcast};
+
+#[tokio::test]
+async fn if_different_with_embedded_metadata() {
+    let directory = common::temp_path("options");
+    let podcast = Podcast {
+        episodes: vec![Episode {
+            link: "http://localhost:1/tagged.mp3".to_string(),
+            ..common::episode("Tagged")
+        }],
+        ..common::podcast()
+    };
+    let options = WriteOptions {
+        overwrite: Overwrite::IfDifferent,
+        embed_metadata: true,
+        ..common::options(&format!("{}/{{episode_title}}.mp3", directory.display()))
+    };
+    assert!(matches!(options.validate(), Err(Error::ConflictingOptions(_))));
+    assert!(matches!(download_podcast(&podcast, &options).await, Err(Error::ConflictingOptions(_))));
+    assert!(matches!(plan_podcast(&podcast, &options, false).await, Err(Error::ConflictingOptions(_))));
+    // Nothing was written before the options were checked
+    assert!(!directory.exists());
+
+    let options = WriteOptions { embed_metadata: false, ..options };
+    assert!(options.validate().is_ok());
+}
+
+#[test]
+fn audiobookshelf_embeds_metadata() {
+    let options = WriteOptions { media_server: Some(MediaServer::Audiobookshelf), ..Default::default() };
+    assert!(options.embeds_metadata());
+    assert!(options.validate().is_ok());
+    let options = WriteOptions { overwrite: Overwrite::IfDifferent, ..options };
+    assert!(matches!(options.validate(), Err(Error::ConflictingOptions(_))));
+    let options = WriteOptions { media_server: Some(MediaServer::Kodi), ..options };
+    assert!(!options.embeds_metadata());
+    assert!(options.validate().is_ok());
+}